@@ -0,0 +1,41 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use tccst::gamestate::{Game, GameOptions};
+
+// 2000 words of five chars, typed without a mistake.
+fn words() -> Vec<String> {
+    (0..2000).map(|i| format!("w{:04}", i)).collect()
+}
+
+fn bench_feed(c: &mut Criterion) {
+    let words = words();
+    let text = words.join(" ");
+
+    let mut group = c.benchmark_group("feed");
+    group.throughput(Throughput::Elements(text.chars().count() as u64));
+
+    group.bench_function("correct text", |b| {
+        b.iter(|| {
+            let mut game = Game::new(&words, GameOptions::default()).unwrap();
+            game.feed(&text);
+            game
+        })
+    });
+
+    let strict = GameOptions {
+        strict: true,
+        ..GameOptions::default()
+    };
+    group.bench_function("correct text (strict)", |b| {
+        b.iter(|| {
+            let mut game = Game::new(&words, strict).unwrap();
+            game.feed(&text);
+            game
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_feed);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+use std::fs::{create_dir_all, write};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use tccst::config::Config;
+use tccst::words::{find_files, FileFilter};
+
+// 50 directories with 100 files each, half of them `.rs` files.
+fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+
+    for d in 0..50 {
+        let sub = dir.path().join(format!("module_{}", d));
+        create_dir_all(&sub).unwrap();
+        for f in 0..100 {
+            let ext = if f % 2 == 0 { "rs" } else { "txt" };
+            write(sub.join(format!("file_{}.{}", f, ext)), "fn main() {}").unwrap();
+        }
+    }
+
+    dir
+}
+
+fn bench_find_files(c: &mut Criterion) {
+    let dir = project();
+    let args = vec![
+        "tccst".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+    ];
+    let config = Config::from_iter(args.into_iter()).unwrap();
+    let filter = FileFilter::new(&config).unwrap();
+
+    c.bench_function("find_files (no limit)", |b| {
+        b.iter(|| find_files(dir.path().into(), &filter, 0).count())
+    });
+
+    c.bench_function("find_files (max 500)", |b| {
+        b.iter(|| find_files(dir.path().into(), &filter, 500).count())
+    });
+}
+
+criterion_group!(benches, bench_find_files);
+criterion_main!(benches);
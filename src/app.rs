@@ -0,0 +1,892 @@
+use std::time::{Duration, Instant};
+
+use tinybit::events::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::{Config, ResultPause};
+use crate::error::Result;
+use crate::gamestate::{Game, GameOptions, GameState};
+use crate::history::{self, RunRecord};
+use crate::replay::{self, Replay};
+use crate::words::Selector;
+
+/// The result of one round, see `--rounds`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Round {
+    pub wpm: usize,
+    pub accuracy: f32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+// -----------------------------------------------------------------------------
+//     - App -
+// -----------------------------------------------------------------------------
+/// Everything the event loop needs to play a session:
+/// the config, the current game and the words it was made from.
+pub struct App {
+    pub config: Config,
+    pub game: Game,
+    selected_words: Vec<String>,
+    /// Selects the words of every new game.
+    pub selector: Selector,
+    /// Summary of the last finished game, see `--share`.
+    pub summary: Option<String>,
+    /// Results of the rounds played so far in this session.
+    pub rounds: Vec<Round>,
+    /// Result of the game finished before this one, to compare with.
+    pub previous: Option<Round>,
+    /// Show the results of a game below the minimum accuracy anyway.
+    pub details: bool,
+    /// Ctrl-C was pressed once while running, with `--confirm-quit`.
+    pub confirm_quit: bool,
+    // When the next round starts, with a `--result-pause` to wait for.
+    next_round_at: Option<Instant>,
+    // The last char key and when it came, for `--debounce-ms`.
+    last_char: Option<(char, Instant)>,
+}
+
+impl App {
+    pub fn new(config: Config, max_len: usize) -> Result<Self> {
+        let mut selector = Selector::new(&config, max_len)?;
+        let words = selector.next()?;
+        let game = Game::new(&words, GameOptions::from(&config))?;
+
+        let inst = Self {
+            config,
+            game,
+            selected_words: words,
+            selector,
+            summary: None,
+            rounds: Vec::new(),
+            previous: None,
+            details: false,
+            confirm_quit: false,
+            next_round_at: None,
+            last_char: None,
+        };
+
+        Ok(inst)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Result<Flow> {
+        if self.bounced(key) {
+            return Ok(Flow::Continue);
+        }
+
+        let was_finished = self.game.is_finished();
+        let flow = self.apply_key(key)?;
+
+        if let Some(summary) = summary(&self.game, &self.config) {
+            self.summary = Some(summary);
+        }
+
+        // Only record the game once, as it finishes.
+        if was_finished {
+            return Ok(flow);
+        }
+
+        if let (Some(path), Some(record)) =
+            (&self.config.log, RunRecord::new(&self.game, &self.config))
+        {
+            history::append(path, &record)?;
+        }
+
+        if let (Some(path), Some(record), GameState::Finished { word_speeds, .. }) = (
+            &self.config.markdown,
+            RunRecord::exported(&self.game, &self.config),
+            &self.game.state,
+        ) {
+            history::write_markdown(path, &record, word_speeds, &self.config)?;
+        }
+
+        // Every finished run, the last one replaces the one before.
+        if let (Some(path), true) = (&self.config.record, self.game.is_finished()) {
+            replay::write(path, &Replay::new(&self.selected_words, &self.game))?;
+        }
+
+        if let GameState::Finished { wpm, accuracy, .. } = self.game.state {
+            if self.config.rounds > 1 {
+                self.rounds.push(Round { wpm, accuracy });
+
+                // Move on to the next round, like pressing `y`,
+                // once the results were up for the `--result-pause`.
+                if !self.rounds_done() {
+                    match self.config.result_pause {
+                        ResultPause::Wait(pause) if pause.is_zero() => self.next_round()?,
+                        ResultPause::Wait(pause) => {
+                            self.next_round_at = Some(Instant::now() + pause)
+                        }
+                        ResultPause::UntilKey => (),
+                    }
+                }
+            }
+        }
+
+        Ok(flow)
+    }
+
+    fn apply_key(&mut self, key: KeyEvent) -> Result<Flow> {
+        // A second Ctrl-C or `y` quits, any other key goes back to the game
+        // and is otherwise ignored.
+        if self.confirm_quit {
+            self.confirm_quit = false;
+            return match key.code {
+                KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => Ok(Flow::Quit),
+                KeyCode::Char('y') => Ok(Flow::Quit),
+                _ => Ok(Flow::Continue),
+            };
+        }
+
+        match key {
+            // Input can only change while the game is running,
+            // not behind the results screen.
+            KeyEvent {
+                code: KeyCode::Char('w'),
+                modifiers: KeyModifiers::CONTROL,
+            } if self.game.is_running() => self.game.pop_word(),
+            // Ask first, rather than lose a good run.
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            } if self.config.confirm_quit && self.game.is_running() => self.confirm_quit = true,
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            } => return Ok(Flow::Quit),
+            // Between rounds any other key moves on with `--result-pause until-key`,
+            // and is ignored otherwise, rather than starting over with `y` or `r`.
+            _ if self.between_rounds() && self.config.result_pause == ResultPause::UntilKey => {
+                self.next_round()?
+            }
+            _ if self.between_rounds() => (),
+            // Restart a bad run with the same words.
+            KeyEvent {
+                code: KeyCode::Char('r'),
+                modifiers: KeyModifiers::CONTROL,
+            } if self.game.is_running() => self.new_game()?,
+            // Like Esc, a way out of strict mode after a wrong last char.
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::CONTROL,
+            } if Some(c) == self.config.finish_key && self.game.is_running() => self.game.finish(),
+            KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            } => match self.game.state {
+                GameState::Finished { .. } => match c {
+                    'y' => {
+                        self.rounds.clear();
+                        self.select_words()?;
+                        self.new_game()?;
+                        self.game.start();
+                    }
+                    'r' => {
+                        let ghost = self.game.typing_times();
+                        self.rounds.clear();
+                        self.new_game()?;
+                        if self.config.ghost {
+                            self.game.set_ghost(ghost);
+                        }
+                        self.game.start();
+                    }
+                    // Back to the start screen with new words, for a break.
+                    'n' if self.config.menu => {
+                        self.rounds.clear();
+                        self.select_words()?;
+                        self.new_game()?;
+                        self.game.stop();
+                    }
+                    'n' | 'q' => return Ok(Flow::Quit),
+                    'd' => self.details = !self.details,
+                    _ => {}
+                },
+                GameState::Running(_) => self.game.push(c),
+                GameState::Stopped => self.game.start(),
+            },
+            // Show the results of what was typed so far.
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } if self.game.is_running() => self.game.finish(),
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } if self.game.is_stopped() => self.game.start(),
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } if self.game.is_running() => self.game.pop(),
+            _ => (),
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    /// True once every round of a `--rounds` session is played.
+    pub fn rounds_done(&self) -> bool {
+        self.config.rounds > 1 && self.rounds.len() >= self.config.rounds
+    }
+
+    // True for the same char as the last key within `--debounce-ms` of it,
+    // as a stuck key repeats, while typing. Off with a debounce of zero.
+    fn bounced(&mut self, key: KeyEvent) -> bool {
+        let c = match key {
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers,
+            } if modifiers != KeyModifiers::CONTROL => c,
+            _ => return false,
+        };
+
+        let now = Instant::now();
+        let debounce = Duration::from_millis(self.config.debounce_ms);
+        let last = self.last_char.replace((c, now));
+
+        self.game.is_running()
+            && !debounce.is_zero()
+            && matches!(last, Some((last, at)) if last == c && now.duration_since(at) < debounce)
+    }
+
+    // A round of a `--rounds` session is finished, and the next one not started.
+    fn between_rounds(&self) -> bool {
+        self.game.is_finished() && !self.rounds.is_empty() && !self.rounds_done()
+    }
+
+    pub fn tick(&mut self, now: Instant) -> Result<()> {
+        self.game.tick(now);
+
+        if matches!(self.next_round_at, Some(at) if now >= at) {
+            self.next_round()?;
+        }
+
+        Ok(())
+    }
+
+    fn next_round(&mut self) -> Result<()> {
+        self.next_round_at = None;
+        self.select_words()?;
+        self.new_game()?;
+        self.game.start();
+        Ok(())
+    }
+
+    fn select_words(&mut self) -> Result<()> {
+        self.selected_words = self.selector.next()?;
+        Ok(())
+    }
+
+    /// The name of the project the words came from,
+    /// only if there are several to choose from.
+    pub fn project_label(&self) -> Option<String> {
+        if self.config.project_paths.len() < 2 {
+            return None;
+        }
+
+        let project = self.selector.project()?;
+        let name = project
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| project.display().to_string());
+        Some(name)
+    }
+
+    fn new_game(&mut self) -> Result<()> {
+        if let GameState::Finished { wpm, accuracy, .. } = self.game.state {
+            self.previous = Some(Round { wpm, accuracy });
+        }
+
+        self.game = Game::new(&self.selected_words, GameOptions::from(&self.config))?;
+        self.details = false;
+        Ok(())
+    }
+}
+
+/// A single line describing a finished game, e.g.
+/// `tccst: 82 wpm / 97.3% acc / 50 words / rs / seed 42`.
+/// There is no summary if the accuracy is below the minimum accuracy.
+pub fn summary(game: &Game, config: &Config) -> Option<String> {
+    match game.state {
+        GameState::Finished {
+            wpm,
+            accuracy,
+            word_count,
+            ..
+        } => match config.min_accuracy {
+            Some(acc) if accuracy < acc => None,
+            _ => {
+                let mut summary = format!(
+                    "tccst: {} wpm / {} acc / {} words / {}",
+                    wpm,
+                    config.accuracy(accuracy, 1),
+                    word_count,
+                    config.file_extension
+                );
+
+                // So others can play the same words.
+                match (&config.daily, config.seed) {
+                    (Some(date), _) => summary.push_str(&format!(" / daily {}", date)),
+                    (None, Some(seed)) => summary.push_str(&format!(" / seed {}", seed)),
+                    (None, None) => {}
+                }
+
+                Some(summary)
+            }
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use tinybit::render::RenderTarget;
+    use tinybit::{Pixel, Renderer, ScreenPos, ScreenSize, Viewport};
+
+    use super::*;
+    use crate::render::render;
+
+    const WIDTH: u16 = 80;
+    const HEIGHT: u16 = 24;
+
+    // Captures every drawn cell into a grid of chars.
+    #[derive(Clone)]
+    pub struct CaptureTarget {
+        pub cells: Rc<RefCell<Vec<Vec<char>>>>,
+    }
+
+    impl CaptureTarget {
+        pub fn new(size: ScreenSize) -> Self {
+            let row = vec![' '; size.width as usize];
+            let cells = vec![row; size.height as usize];
+            Self {
+                cells: Rc::new(RefCell::new(cells)),
+            }
+        }
+
+        pub fn lines(&self) -> Vec<String> {
+            self.cells
+                .borrow()
+                .iter()
+                .map(|row| row.iter().collect::<String>().trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        }
+    }
+
+    impl RenderTarget for CaptureTarget {
+        fn render(&mut self, pixels: Vec<Pixel>) {
+            let mut cells = self.cells.borrow_mut();
+            for pixel in pixels {
+                let ScreenPos { x, y, .. } = pixel.pos;
+                cells[y as usize][x as usize] = pixel.glyph;
+            }
+        }
+
+        fn clear(&mut self) {
+            self.cells
+                .borrow_mut()
+                .iter_mut()
+                .for_each(|row| row.iter_mut().for_each(|c| *c = ' '));
+        }
+    }
+
+    // The only file of the harness project. Short plain words,
+    // so the tests don't depend on what the words are.
+    const FIXTURE: &str =
+        "fn add(a: u8, b: u8) -> u8 {\n    a + b\n}\n\nfn one() -> u8 {\n    1\n}\n";
+
+    // A project with a single file of `FIXTURE`, named `src`.
+    pub fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src").join("lib.rs"), FIXTURE).unwrap();
+        dir
+    }
+
+    // A seeded session over the `fixture` project,
+    // rendered into a `CaptureTarget`.
+    pub struct Harness {
+        pub app: App,
+        pub screen: CaptureTarget,
+        viewport: Viewport,
+        renderer: Renderer<CaptureTarget>,
+        _project: tempfile::TempDir,
+    }
+
+    impl Harness {
+        // A `--seed` in the `args` replaces the default one.
+        pub fn new(args: &str) -> Self {
+            let project = fixture();
+            let args = format!(
+                "tccst --seed 1 {} {}",
+                args,
+                project.path().join("src").display()
+            );
+            let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+            let size = ScreenSize::new(WIDTH, HEIGHT);
+            let screen = CaptureTarget::new(size);
+
+            let mut inst = Self {
+                app: App::new(config, (WIDTH * HEIGHT) as usize).unwrap(),
+                viewport: Viewport::new(ScreenPos::zero(), size),
+                renderer: Renderer::new(screen.clone()),
+                screen,
+                _project: project,
+            };
+            inst.render();
+            inst
+        }
+
+        fn render(&mut self) {
+            render(&self.app, &mut self.viewport, &mut self.renderer);
+        }
+
+        pub fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Flow {
+            let flow = self.app.handle_key(KeyEvent { code, modifiers }).unwrap();
+            self.render();
+            flow
+        }
+
+        pub fn type_str(&mut self, s: &str) {
+            s.chars().for_each(|c| {
+                self.key(KeyCode::Char(c), KeyModifiers::NONE);
+            });
+        }
+
+        pub fn type_text(&mut self) {
+            let text = self.app.game.text.clone();
+            self.type_str(&text);
+        }
+    }
+
+    #[test]
+    fn finish_and_quit() {
+        let mut harness = Harness::new("-w 3");
+        let first = harness.app.game.text.chars().next().unwrap();
+        assert!(harness.screen.lines()[0].starts_with(first));
+
+        harness.type_text();
+        assert!(matches!(
+            harness.app.game.state,
+            GameState::Finished { mistakes: 0, .. }
+        ));
+        assert!(harness
+            .screen
+            .lines()
+            .iter()
+            .any(|l| l.starts_with("Try again?")));
+
+        assert_eq!(
+            harness.key(KeyCode::Char('n'), KeyModifiers::NONE),
+            Flow::Quit
+        );
+    }
+
+    #[test]
+    fn retry_same_words() {
+        let mut harness = Harness::new("-w 3");
+        let text = harness.app.game.text.clone();
+        harness.type_text();
+
+        harness.key(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_running());
+        assert_eq!(harness.app.game.text, text);
+        assert!(harness.app.game.input().is_empty());
+    }
+
+    #[test]
+    fn retry_races_the_ghost() {
+        let mut harness = Harness::new("-w 3 --ghost");
+        harness.type_text();
+        let typed = harness.app.game.typing_times();
+
+        harness.key(KeyCode::Char('r'), KeyModifiers::NONE);
+        harness.type_str("x");
+        assert_eq!(
+            harness
+                .app
+                .game
+                .ghost_index(Instant::now() + Duration::from_secs(600)),
+            Some(typed.len())
+        );
+    }
+
+    #[test]
+    fn new_words() {
+        let mut harness = Harness::new("-w 3");
+        harness.type_text();
+        harness.key(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_running());
+        assert!(harness.app.game.input().is_empty());
+        assert_eq!(harness.app.game.text.split(' ').count(), 3);
+    }
+
+    #[test]
+    fn ctrl_c_quits() {
+        let mut harness = Harness::new("-w 3");
+        harness.type_str("x");
+        assert_eq!(
+            harness.key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Flow::Quit
+        );
+    }
+
+    #[test]
+    fn confirm_quit() {
+        let mut harness = Harness::new("-w 3 --confirm-quit");
+        harness.type_str("x");
+        let ctrl_c = |harness: &mut Harness| harness.key(KeyCode::Char('c'), KeyModifiers::CONTROL);
+
+        assert_eq!(ctrl_c(&mut harness), Flow::Continue);
+        assert!(harness.app.confirm_quit);
+        assert_eq!(ctrl_c(&mut harness), Flow::Quit);
+
+        ctrl_c(&mut harness);
+        assert_eq!(
+            harness.key(KeyCode::Char('y'), KeyModifiers::NONE),
+            Flow::Quit
+        );
+
+        // Anything else goes back to the game, without typing the key.
+        ctrl_c(&mut harness);
+        assert_eq!(
+            harness.key(KeyCode::Char('n'), KeyModifiers::NONE),
+            Flow::Continue
+        );
+        assert!(!harness.app.confirm_quit);
+        assert_eq!(harness.app.game.input().len(), 1);
+
+        // Nothing to lose on the results screen.
+        harness.app.game.finish();
+        assert_eq!(ctrl_c(&mut harness), Flow::Quit);
+    }
+
+    #[test]
+    fn retry_clock_starts_on_first_key() {
+        let mut harness = Harness::new("-w 3");
+        harness.type_text();
+        harness.key(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_running());
+
+        std::thread::sleep(Duration::from_millis(50));
+        harness.type_str("x");
+        let GameState::Running(start) = harness.app.game.state else {
+            panic!("not running")
+        };
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn share_summary() {
+        let mut harness = Harness::new("-w 3");
+        assert_eq!(harness.app.summary, None);
+
+        harness.type_text();
+        let summary = harness.app.summary.clone().unwrap();
+        assert!(summary.starts_with("tccst: "));
+        assert!(summary.ends_with("% acc / 3 words / rs / seed 1"));
+
+        // Starting a new game keeps the summary of the last finished one.
+        harness.key(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert_eq!(harness.app.summary, Some(summary));
+    }
+
+    #[test]
+    fn log_finished_runs_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.tsv");
+        // The harness types faster than a paste.
+        let args = format!(
+            "-w 3 --min-record-secs 0 --paste-threshold 0 --log {}",
+            path.display()
+        );
+        let mut harness = Harness::new(&args);
+        harness.type_text();
+        // Keys on the results screen don't record it again.
+        harness.key(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        // Instant runs are too short to record by default.
+        let args = format!("-w 3 --log {}", path.display());
+        let mut harness = Harness::new(&args);
+        harness.type_text();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn rounds_average() {
+        let mut harness = Harness::new("-w 2 --rounds 3");
+        harness.type_text();
+        assert!(harness.app.game.is_running());
+        harness.type_text();
+        assert_eq!(harness.app.rounds.len(), 2);
+        assert!(!harness.app.rounds_done());
+
+        harness.type_text();
+        assert!(harness.app.rounds_done());
+        let lines = harness.screen.lines();
+        assert!(lines.iter().any(|l| l.starts_with("round 3: ")));
+        assert!(lines.iter().any(|l| l.starts_with("average: ")));
+
+        // A new session starts from the first round.
+        harness.key(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert!(harness.app.rounds.is_empty());
+        assert_eq!(
+            harness.key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Flow::Quit
+        );
+    }
+
+    #[test]
+    fn ctrl_r_restarts() {
+        let mut harness = Harness::new("-w 3");
+        let text = harness.app.game.text.clone();
+        harness.type_str("xx");
+        assert!(!harness.app.game.input().is_empty());
+
+        harness.key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(harness.app.game.is_running());
+        assert_eq!(harness.app.game.text, text);
+        assert!(harness.app.game.input().is_empty());
+
+        // A clean run after the restart has no mistakes from before it.
+        harness.type_text();
+        assert!(matches!(
+            harness.app.game.state,
+            GameState::Finished { mistakes: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn backspace_after_finish() {
+        let mut harness = Harness::new("-w 3");
+        harness.type_text();
+        let input = harness.app.game.input().to_vec();
+        let lines = harness.screen.lines();
+
+        harness.key(KeyCode::Backspace, KeyModifiers::NONE);
+        harness.key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert!(harness.app.game.is_finished());
+        assert_eq!(harness.app.game.input(), input);
+        assert_eq!(harness.screen.lines(), lines);
+    }
+
+    #[test]
+    fn label_with_several_projects() {
+        let harness = Harness::new("-w 3");
+        assert_eq!(harness.app.project_label(), None);
+
+        let other = fixture();
+        let harness = Harness::new(&format!("-w 3 {}", other.path().join("src").display()));
+        assert_eq!(harness.app.project_label(), Some("src".to_string()));
+        assert!(harness.screen.lines()[0].ends_with("src"));
+    }
+
+    #[test]
+    fn escape_finishes_early() {
+        let mut harness = Harness::new("-w 3");
+        let text = harness.app.game.text.clone();
+        let half = text
+            .chars()
+            .take(text.chars().count() / 2)
+            .collect::<String>();
+        harness.type_str(&half);
+
+        harness.key(KeyCode::Esc, KeyModifiers::NONE);
+        assert!(matches!(
+            harness.app.game.state,
+            GameState::Finished {
+                mistakes: 0,
+                accuracy,
+                ..
+            } if accuracy == 100.0
+        ));
+        assert!(harness
+            .screen
+            .lines()
+            .iter()
+            .any(|l| l.starts_with("Try again?")));
+
+        harness.key(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_running());
+    }
+
+    #[test]
+    fn markdown_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.md");
+        // Short runs are exported too.
+        let args = format!("-w 3 --paste-threshold 0 --markdown {}", path.display());
+        let mut harness = Harness::new(&args);
+        harness.type_text();
+        let md = std::fs::read_to_string(&path).unwrap();
+        assert!(md.starts_with("| extension | wpm |"));
+
+        // Rejected runs write nothing.
+        std::fs::remove_file(&path).unwrap();
+        let args = format!(
+            "-w 3 -ma 101 --min-record-secs 0 --markdown {}",
+            path.display()
+        );
+        let mut harness = Harness::new(&args);
+        harness.type_text();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn record_keystrokes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.keys");
+        let mut harness = Harness::new(&format!("-w 3 --record {}", path.display()));
+        harness.type_text();
+
+        let replay = replay::read(&path).unwrap();
+        assert_eq!(replay.words, harness.app.selected_words);
+        assert_eq!(replay.times.len(), harness.app.game.text_chars.len());
+    }
+
+    #[test]
+    fn toggle_details() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.tsv");
+        let args = format!("-w 3 -ma 101 --min-record-secs 0 --log {}", path.display());
+        let mut harness = Harness::new(&args);
+        harness.type_text();
+
+        harness.key(KeyCode::Char('d'), KeyModifiers::NONE);
+        assert!(harness.app.details);
+        harness.key(KeyCode::Char('d'), KeyModifiers::NONE);
+        assert!(!harness.app.details);
+
+        // Still not recorded.
+        assert!(!path.exists());
+
+        harness.key(KeyCode::Char('d'), KeyModifiers::NONE);
+        harness.key(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert!(!harness.app.details);
+    }
+
+    #[test]
+    fn finish_key_in_strict_mode() {
+        let mut harness = Harness::new("-w 1 -s --finish-key g");
+        // All but the last char, and then a wrong one.
+        let text = harness.app.game.text_chars.clone();
+        let head = text[..text.len() - 1].iter().collect::<String>();
+        harness.type_str(&head);
+        harness.type_str("\u{1}");
+        assert!(harness.app.game.is_running());
+
+        harness.key(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        let GameState::Finished { mistakes, .. } = harness.app.game.state else {
+            panic!("not finished")
+        };
+        assert_eq!(mistakes, 1);
+    }
+
+    #[test]
+    fn back_to_the_menu() {
+        let mut harness = Harness::new("-w 3 --menu");
+        harness.type_text();
+
+        assert_eq!(
+            harness.key(KeyCode::Char('n'), KeyModifiers::NONE),
+            Flow::Continue
+        );
+        assert!(harness.app.game.is_stopped());
+        assert_eq!(harness.screen.lines(), vec!["Press any key to start"]);
+
+        harness.key(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_running());
+        assert!(harness.app.game.input().is_empty());
+
+        assert_eq!(
+            harness.key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Flow::Quit
+        );
+    }
+
+    #[test]
+    fn result_pause() {
+        let mut harness = Harness::new("-w 2 --rounds 3 --result-pause 2000");
+        harness.type_text();
+        assert!(harness.app.game.is_finished());
+
+        // Keys don't skip the pause.
+        harness.key(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_finished());
+        assert_eq!(harness.app.rounds.len(), 1);
+
+        let now = Instant::now();
+        harness.app.tick(now).unwrap();
+        assert!(harness.app.game.is_finished());
+        harness.app.tick(now + Duration::from_secs(2)).unwrap();
+        assert!(harness.app.game.is_running());
+        assert!(harness.app.game.input().is_empty());
+
+        let mut harness = Harness::new("-w 2 --rounds 3 --result-pause until-key");
+        harness.type_text();
+        harness.app.tick(now + Duration::from_secs(60)).unwrap();
+        assert!(harness.app.game.is_finished());
+        harness.key(KeyCode::Char('n'), KeyModifiers::NONE);
+        assert!(harness.app.game.is_running());
+        assert_eq!(harness.app.rounds.len(), 1);
+    }
+
+    #[test]
+    fn debounce_repeated_keys() {
+        let mut harness = Harness::new("-w 3 --debounce-ms 10000");
+        harness.type_str("xxxyy");
+        assert_eq!(harness.app.game.input().len(), 2);
+
+        // Only the same key is ignored.
+        harness.type_str("xyx");
+        assert_eq!(harness.app.game.input().len(), 5);
+
+        let mut harness = Harness::new("-w 3");
+        harness.type_str("xxx");
+        assert_eq!(harness.app.game.input().len(), 3);
+
+        let mut harness = Harness::new("-w 3 --debounce-ms 5");
+        harness.type_str("x");
+        std::thread::sleep(Duration::from_millis(10));
+        harness.type_str("x");
+        assert_eq!(harness.app.game.input().len(), 2);
+    }
+
+    #[test]
+    fn compare_with_previous_run() {
+        let mut harness = Harness::new("-w 3");
+        let compared = |harness: &Harness| {
+            harness
+                .screen
+                .lines()
+                .iter()
+                .any(|l| l.starts_with("since the last run: wpm "))
+        };
+
+        harness.type_text();
+        assert_eq!(harness.app.previous, None);
+        assert!(!compared(&harness));
+
+        harness.key(KeyCode::Char('y'), KeyModifiers::NONE);
+        let Some(Round { accuracy, .. }) = harness.app.previous else {
+            panic!("the first run is the previous one");
+        };
+        assert_eq!(accuracy, 100.0);
+
+        harness.type_str("x");
+        harness.key(KeyCode::Esc, KeyModifiers::NONE);
+        assert!(compared(&harness));
+        assert!(harness
+            .screen
+            .lines()
+            .iter()
+            .any(|l| l.ends_with("| accuracy -100.00%")));
+    }
+}
@@ -1,31 +1,226 @@
 use std::env::Args;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::daily;
 use crate::error::{Error, Result};
+use crate::gamestate::{DEFAULT_EDIT_DISTANCE_LIMIT, DEFAULT_WPM_DIVISOR};
+use crate::palette::Palette;
+use crate::words::{self, glob_set};
 use tinybit::Color;
 
-#[derive(Debug)] 
+/// How long the results of a round stay up before the next round, see `--rounds`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultPause {
+    /// Move on after this long, right away if zero.
+    Wait(Duration),
+    /// Move on with the next key.
+    UntilKey,
+}
+
+impl Default for ResultPause {
+    fn default() -> Self {
+        ResultPause::Wait(Duration::ZERO)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
-    pub project_path: PathBuf,
+    /// Every game picks one of these projects at random.
+    pub project_paths: Vec<PathBuf>,
     pub file_extension: String,
     pub word_count: usize,
     pub strict: bool,
     pub skip_word_on_space: bool,
     pub min_accuracy: Option<f32>,
-    pub cursor_foreground_color: Color,
-    pub cursor_background_color: Color,
+    pub palette: Palette,
+    pub paste_threshold: usize,
+    pub tab_width: usize,
+    pub max_files: usize,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub name_regex: Option<String>,
+    pub max_chars: Option<usize>,
+    pub show_whitespace: bool,
+    pub share: bool,
+    pub seed: Option<u64>,
+    /// The date of the daily challenge, see `--daily`.
+    pub daily: Option<String>,
+    /// Append finished runs to this file, see `history`.
+    pub log: Option<PathBuf>,
+    /// Runs shorter than this are not recorded.
+    pub min_record_secs: f32,
+    /// Number of games in a session, see `--rounds`.
+    pub rounds: usize,
+    /// Extensions of files that are prose rather than code, see `words::prose_to_words`.
+    pub prose_extensions: Vec<String>,
+    /// Prefer words with digits or symbols in them.
+    pub symbols: bool,
+    /// Pick the words from this file rather than from the project.
+    pub words_file: Option<PathBuf>,
+    pub shuffle: bool,
+    /// Speed of the pacer in wpm, see `--pace`.
+    pub pace: Option<f32>,
+    /// Capitalize some of the words, see `--caps`.
+    pub caps: bool,
+    /// Leave out `#[...]` and `#![...]` lines.
+    pub strip_attributes: bool,
+    /// Characters per word when converting cpm to wpm.
+    pub wpm_divisor: f32,
+    /// Never select fewer words than this, whatever the word count.
+    pub min_words: usize,
+    /// Show what the colours mean while typing.
+    pub legend: bool,
+    /// Type whole lines exactly as they are in the file, see `--verbatim`.
+    pub verbatim: bool,
+    /// Strip comments from the lines in verbatim mode.
+    pub strip_comments: bool,
+    /// Pick larger files more often, see `words::file_weight`.
+    pub weight_by_size: bool,
+    /// Columns and rows left empty around the edges of the screen.
+    pub padding: u16,
+    /// Write the result of every recorded run to this file, as markdown.
+    pub markdown: Option<PathBuf>,
+    /// Write the keystrokes of every finished run to this file, see `replay::Replay`.
+    pub record: Option<PathBuf>,
+    /// Play back two `record` files side by side instead of playing.
+    pub compare: Option<(PathBuf, PathBuf)>,
+    /// Show the stats of the `log` instead of playing.
+    pub stats: bool,
+    /// Label written with every logged run, and the only runs counted by `stats`.
+    pub tag: Option<String>,
+    /// Type every word of this many consecutive lines, instead of `word_count` words.
+    pub lines: Option<usize>,
+    /// Copy the summary of every finished game to the clipboard, see `clipboard::osc52`.
+    pub copy: bool,
+    /// Start the words at this word of the file, instead of a random one.
+    pub offset: Option<usize>,
+    /// Ask before Ctrl-C quits a running game.
+    pub confirm_quit: bool,
+    /// Only finish once the text is typed exactly, see `GameOptions`.
+    pub require_exact_end: bool,
+    /// Files with exactly these names are used too, e.g. `Makefile`.
+    pub file_names: Vec<String>,
+    /// Show how much of the text is typed while typing.
+    pub percent: bool,
+    /// Print the selected words instead of playing.
+    pub dry_run: bool,
+    /// Ctrl and this key finishes a running game, like Esc.
+    pub finish_key: Option<char>,
+    /// Keep the indentation of every line, with `verbatim` or `lines`.
+    pub keep_indent: bool,
+    /// Decimals of the accuracy in the results and exports, each has its own default.
+    pub decimals: Option<usize>,
+    /// Leave the labels and the `%` out of the results.
+    pub no_units: bool,
+    /// `n` on the results screen goes back to the start screen instead of quitting.
+    pub menu: bool,
+    /// Show the wpm of the last few seconds while typing.
+    pub live_wpm: bool,
+    /// Only use the lines added in the uncommitted changes of the project.
+    pub git_diff: bool,
+    /// How long the results of a round stay up in a `--rounds` session.
+    pub result_pause: ResultPause,
+    /// Ignore the same char key arriving within this many ms of the last, 0 disables.
+    pub debounce_ms: u64,
+    /// The wpm a run without mistakes needs to be a perfect run, 0 for any.
+    pub perfect_wpm: usize,
+    /// Take control chars and invisible spaces out of the words.
+    pub printable_only: bool,
+    /// Only use words made of nothing but these chars.
+    pub chars: Option<String>,
+    /// More chars that split words, dropped like spaces. Verbatim lines aren't split.
+    pub word_sep: Option<String>,
+    /// Pause the clock after this long without a key.
+    pub idle_timeout: Option<Duration>,
+    /// Pick the words from anywhere in the file, instead of a run of consecutive words.
+    pub scatter: bool,
+    /// Race a cursor typing like the last run, when the same words are retried.
+    pub ghost: bool,
+    /// Texts longer than this many chars don't get an accuracy by edit distance.
+    pub edit_distance_limit: usize,
+    /// Fade the background of this many typed cells before the cursor.
+    pub trail: Option<usize>,
+    /// Only use the files listed in this file, one per line.
+    pub files_from: Option<PathBuf>,
+    /// Only use the files of `files_from` that pass the file filters.
+    pub filter_listed: bool,
 }
 
 impl Config {
     pub fn from_iter(mut args: impl Iterator<Item = String>) -> Result<Self> {
         let mut word_count = 10;
-        let mut project_path = None;
+        let mut project_paths: Vec<PathBuf> = Vec::new();
         let mut file_extension = "rs".to_string();
         let mut foreground_color = None;
         let mut background_color = None;
+        let mut palette = Palette::default();
+        let mut paste_threshold = 5;
+        let mut tab_width = 4;
+        let mut padding = 0;
+        let mut max_files = 0;
+        let mut include = Vec::new();
+        let mut file_names = Vec::new();
+        let mut exclude = Vec::new();
+        let mut name_regex = None;
+        let mut max_chars = None;
+        let mut show_whitespace = false;
+        let mut share = false;
+        let mut copy = false;
+        let mut confirm_quit = false;
+        let mut require_exact_end = false;
+        let mut percent = false;
+        let mut dry_run = false;
+        let mut finish_key = None;
+        let mut keep_indent = false;
+        let mut decimals = None;
+        let mut no_units = false;
+        let mut menu = false;
+        let mut live_wpm = false;
+        let mut git_diff = false;
+        let mut result_pause = ResultPause::default();
+        let mut debounce_ms = 0;
+        let mut perfect_wpm = 0;
+        let mut printable_only = false;
+        let mut chars = None;
+        let mut word_sep = None;
+        let mut idle_timeout = None;
+        let mut scatter = false;
+        let mut ghost = false;
+        let mut edit_distance_limit = DEFAULT_EDIT_DISTANCE_LIMIT;
+        let mut trail = None;
+        let mut files_from: Option<PathBuf> = None;
+        let mut filter_listed = false;
+        let mut offset = None;
+        let mut seed = None;
+        let mut daily = false;
+        let mut log = None;
+        let mut markdown = None;
+        let mut record = None;
+        let mut compare = None;
+        let mut stats = false;
+        let mut tag = None;
+        let mut lines = None;
+        let mut min_record_secs = 2.0;
+        let mut rounds = 1;
+        let mut symbols = false;
+        let mut shuffle = false;
+        let mut pace = None;
+        let mut caps = false;
+        let mut strip_attributes = false;
+        let mut wpm_divisor = DEFAULT_WPM_DIVISOR;
+        let mut min_words = 1;
+        let mut legend = false;
+        let mut verbatim = false;
+        let mut strip_comments = false;
+        let mut weight_by_size = false;
+        let mut words_file: Option<PathBuf> = None;
+        let mut prose_extensions = vec!["txt".to_string(), "md".into()];
         let mut min_accuracy = None;
         let mut skip_word_on_space = false;
 
+        // The first argument is the program name.
+        let _ = args.next();
         let mut argc = 0;
         let mut strict = false;
 
@@ -40,17 +235,198 @@ impl Config {
                         .unwrap_or(10)
                 }
                 "-t" => {
-                    file_extension = args.next().unwrap_or("rs".to_string());
-                    if file_extension.starts_with('.') {
-                        file_extension.remove(0);
+                    file_extension = extension(args.next().unwrap_or("rs".to_string()));
+                }
+                "-ma" => min_accuracy = args.next().and_then(|s| s.parse::<f32>().ok()),
+                "--palette" => {
+                    palette = args.next().unwrap_or_default().parse::<Palette>()?;
+                }
+                "--paste-threshold" => {
+                    paste_threshold = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(5)
+                }
+                "--tab-width" => {
+                    tab_width = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(4)
+                }
+                "--padding" => {
+                    padding = args.next().and_then(|s| s.parse::<u16>().ok()).unwrap_or(0)
+                }
+                "--max-files" => {
+                    max_files = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(0)
+                }
+                "--include" => include.push(args.next().unwrap_or_default()),
+                "--file-name" => file_names.extend(args.next()),
+                "--exclude" => exclude.push(args.next().unwrap_or_default()),
+                "--name-regex" => name_regex = args.next(),
+                "--max-chars" => max_chars = args.next().and_then(|s| s.parse::<usize>().ok()),
+                "--show-whitespace" => show_whitespace = true,
+                "--share" => share = true,
+                "--copy" => copy = true,
+                "--confirm-quit" => confirm_quit = true,
+                "--exact-end" => require_exact_end = true,
+                "--percent" => percent = true,
+                "--dry-run" => dry_run = true,
+                // Handled by `from_args`, before the arguments get here.
+                "--save-config" | "--no-config" => (),
+                "--keep-indent" => keep_indent = true,
+                "--decimals" => decimals = args.next().and_then(|s| s.parse::<usize>().ok()),
+                "--no-units" => no_units = true,
+                "--menu" => menu = true,
+                "--live-wpm" => live_wpm = true,
+                "--git-diff" => git_diff = true,
+                "--filter-listed" => filter_listed = true,
+                "--printable-only" => printable_only = true,
+                "--scatter" => scatter = true,
+                "--ghost" => ghost = true,
+                "--trail" => {
+                    trail = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .filter(|&cells| cells > 0)
+                }
+                "--edit-distance-limit" => {
+                    edit_distance_limit = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(DEFAULT_EDIT_DISTANCE_LIMIT)
+                }
+                "--idle-timeout" => {
+                    idle_timeout = args
+                        .next()
+                        .and_then(|s| s.parse::<u64>().ok())
+                        .filter(|&secs| secs > 0)
+                        .map(Duration::from_secs)
+                }
+                "--chars" => chars = args.next().filter(|chars| !chars.is_empty()),
+                "--word-sep" => word_sep = args.next().filter(|sep| !sep.is_empty()),
+                "--perfect-wpm" => {
+                    perfect_wpm = args.next().and_then(|s| s.parse().ok()).unwrap_or(0)
+                }
+                "--debounce-ms" => {
+                    debounce_ms = args.next().and_then(|s| s.parse::<u64>().ok()).unwrap_or(0)
+                }
+                "--result-pause" => {
+                    result_pause = match args.next().as_deref() {
+                        Some("until-key") => ResultPause::UntilKey,
+                        Some(ms) => match ms.parse::<u64>() {
+                            Ok(ms) => ResultPause::Wait(Duration::from_millis(ms)),
+                            Err(_) => {
+                                return Err(Error::InvalidArgument(format!(
+                                    "--result-pause is a number of ms or until-key, not {}",
+                                    ms
+                                )))
+                            }
+                        },
+                        None => {
+                            return Err(Error::InvalidArgument(
+                                "--result-pause needs a number of ms or until-key".into(),
+                            ))
+                        }
                     }
                 }
-                "-ma" => {
-                    min_accuracy = args
+                "--finish-key" => {
+                    // Ctrl-C, Ctrl-R and Ctrl-W are taken.
+                    finish_key = match args.next().map(|s| s.to_lowercase()) {
+                        Some(key) if key.chars().count() == 1 && !"crw".contains(&key) => {
+                            key.chars().next()
+                        }
+                        key => {
+                            return Err(Error::InvalidArgument(format!(
+                                "--finish-key needs a single key other than c, r or w, not {}",
+                                key.unwrap_or_default()
+                            )))
+                        }
+                    }
+                }
+                "--seed" => seed = args.next().and_then(|s| s.parse::<u64>().ok()),
+                "--daily" => daily = true,
+                "--log" => {
+                    log = args
+                        .next()
+                        .map(|s| shellexpand::tilde(&s).to_string().into())
+                }
+                "--stats" => stats = true,
+                "--tag" => tag = args.next(),
+                "--markdown" => {
+                    markdown = args
+                        .next()
+                        .map(|s| shellexpand::tilde(&s).to_string().into())
+                }
+                "--record" => {
+                    record = args
+                        .next()
+                        .map(|s| shellexpand::tilde(&s).to_string().into())
+                }
+                "--compare" => {
+                    let mut path = || {
+                        args.next()
+                            .map(|s| PathBuf::from(shellexpand::tilde(&s).to_string()))
+                    };
+                    compare = Some((path(), path()))
+                }
+                "--min-record-secs" => {
+                    min_record_secs = args
+                        .next()
+                        .and_then(|s| s.parse::<f32>().ok())
+                        .unwrap_or(2.0)
+                }
+                "--rounds" => {
+                    rounds = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(1)
+                }
+                "--prose" => {
+                    prose_extensions.push(extension(args.next().unwrap_or_default()));
+                }
+                "--symbols" => symbols = true,
+                "--shuffle" => shuffle = true,
+                "--caps" => caps = true,
+                "--strip-attributes" => strip_attributes = true,
+                "--legend" => legend = true,
+                "--verbatim" => verbatim = true,
+                "--strip-comments" => strip_comments = true,
+                "--weight-by-size" => weight_by_size = true,
+                "--min-words" => {
+                    min_words = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(1)
+                }
+                "--wpm-divisor" => {
+                    wpm_divisor = args
                         .next()
                         .and_then(|s| s.parse::<f32>().ok())
+                        .filter(|d| *d > 0.0)
+                        .unwrap_or(DEFAULT_WPM_DIVISOR)
+                }
+                "--offset" => offset = args.next().and_then(|s| s.parse::<usize>().ok()),
+                "--lines" => {
+                    lines = args
+                        .next()
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .filter(|n| *n > 0)
+                }
+                "--pace" => pace = args.next().and_then(|s| s.parse::<f32>().ok()),
+                "--words-file" => {
+                    words_file = args
+                        .next()
+                        .map(|s| shellexpand::tilde(&s).to_string().into())
                 }
-                "-v" => return Err(Error::Version),
+                "--files-from" => {
+                    files_from = args
+                        .next()
+                        .map(|s| shellexpand::tilde(&s).to_string().into())
+                }
+                "-v" | "--version" => return Err(Error::Version),
                 "-s" => strict = true,
                 "-ss" => skip_word_on_space = true,
                 "-cf" => {
@@ -77,33 +453,174 @@ impl Config {
                         }
                     }
                 }
+                "-p" => {
+                    if let Some(arg) = args.next() {
+                        project_paths.push(shellexpand::tilde(&arg).to_string().into());
+                    }
+                }
                 arg => {
                     let path: String = shellexpand::tilde(arg).to_string();
-                    project_path = Some(path);
+                    project_paths.push(path.into());
                 }
             }
         }
 
-        if argc <= 1 {
+        if argc == 0 {
             return Err(Error::NeedsHelp);
         }
 
-        let project_path = match project_path {
-            Some(p) => p,
-            None => return Err(Error::PathMissing),
+        // Without whole lines the indentation would end up anywhere.
+        if keep_indent && !verbatim && lines.is_none() {
+            return Err(Error::InvalidArgument(
+                "--keep-indent needs --verbatim or --lines".into(),
+            ));
+        }
+
+        // Whole lines are typed in order, so nothing picks or reorders the words.
+        if lines.is_some() {
+            let picking = [
+                ("--shuffle", shuffle),
+                ("--scatter", scatter),
+                ("--symbols", symbols),
+                ("--min-words", min_words > 1),
+                ("--offset", offset.is_some()),
+            ];
+            if let Some((flag, _)) = picking.iter().find(|(_, set)| *set) {
+                return Err(Error::InvalidArgument(format!(
+                    "--lines can't be used with {}",
+                    flag
+                )));
+            }
+        }
+
+        // Capitalized words would have chars outside of the set.
+        if caps && chars.is_some() {
+            return Err(Error::InvalidArgument(
+                "--caps can't be used with --chars".into(),
+            ));
+        }
+
+        if filter_listed && files_from.is_none() {
+            return Err(Error::InvalidArgument(
+                "--filter-listed needs --files-from".into(),
+            ));
+        }
+
+        if stats && log.is_none() {
+            return Err(Error::InvalidArgument("--stats needs a --log file".into()));
+        }
+
+        let compare = match compare {
+            Some((Some(first), Some(second))) => Some((first, second)),
+            Some(_) => {
+                return Err(Error::InvalidArgument(
+                    "--compare needs two --record files".into(),
+                ))
+            }
+            None => None,
         };
 
+        // The project isn't needed when the words or the files come from a file,
+        // or there is nothing to play.
+        if project_paths.is_empty() {
+            if words_file.is_none() && files_from.is_none() && !stats && compare.is_none() {
+                return Err(Error::PathMissing);
+            }
+            project_paths.push(".".into());
+        }
+
         if word_count == 0 {
             return Err(Error::ZeroWordCount);
         }
 
+        glob_set(&include)?;
+        glob_set(&exclude)?;
+        if let Some(pattern) = &name_regex {
+            words::name_regex(pattern)?;
+        }
+
+        if let Some(c) = foreground_color {
+            palette.cursor.fg = Some(c);
+        }
+
+        if let Some(c) = background_color {
+            palette.cursor.bg = Some(c);
+        }
+
+        // Everyone gets the same words today.
+        let daily = daily.then(daily::today);
+
+        if let Some(date) = &daily {
+            seed = Some(daily::seed(date));
+        }
+
         let inst = Self {
             word_count,
-            project_path: project_path.into(),
+            project_paths,
             file_extension,
             strict,
-            cursor_foreground_color: foreground_color.unwrap_or(Color::Black),
-            cursor_background_color: background_color.unwrap_or(Color::Blue),
+            palette,
+            paste_threshold,
+            tab_width,
+            max_files,
+            include,
+            exclude,
+            name_regex,
+            max_chars,
+            show_whitespace,
+            share,
+            seed,
+            daily,
+            log,
+            markdown,
+            record,
+            compare,
+            stats,
+            tag,
+            lines,
+            copy,
+            offset,
+            confirm_quit,
+            require_exact_end,
+            file_names,
+            percent,
+            dry_run,
+            finish_key,
+            keep_indent,
+            decimals,
+            no_units,
+            menu,
+            live_wpm,
+            git_diff,
+            result_pause,
+            debounce_ms,
+            perfect_wpm,
+            printable_only,
+            chars,
+            word_sep,
+            idle_timeout,
+            scatter,
+            ghost,
+            edit_distance_limit,
+            trail,
+            files_from,
+            filter_listed,
+            min_record_secs,
+            rounds,
+            prose_extensions,
+            symbols,
+            words_file,
+            shuffle,
+            pace,
+            caps,
+            strip_attributes,
+            wpm_divisor,
+            min_words,
+            legend,
+            verbatim,
+            strip_comments,
+            weight_by_size,
+            padding,
             min_accuracy,
             skip_word_on_space,
         };
@@ -111,11 +628,273 @@ impl Config {
         Ok(inst)
     }
 
+    /// The flags saved with `--save-config` come first, unless `--no-config`
+    /// is given. `--save-config` saves the `Config` of the command line
+    /// in place of the saved flags.
     pub fn from_args(args: Args) -> Result<Self> {
-        Self::from_iter(args)
+        Self::with_config_file(args.collect(), &saved_config_path())
+    }
+
+    fn with_config_file(args: Vec<String>, path: &Path) -> Result<Self> {
+        // Only a `Config` that parses is saved, so it can't break later runs.
+        if args.iter().any(|arg| arg == "--save-config") {
+            let config = Self::from_iter(args.into_iter())?;
+            save_config(path, &config)?;
+            return Ok(config);
+        }
+
+        let saved = if args.iter().any(|arg| arg == "--no-config") {
+            Vec::new()
+        } else {
+            load_config(path)?
+        };
+
+        Self::from_iter(with_saved(args, &saved).into_iter())
+    }
+
+    /// The options of the `Config` as flags for `from_iter`, one flag and
+    /// its value per line. Without the project paths, the word sources
+    /// (`--words-file` and `--files-from`), and `--dry-run` and `--stats`,
+    /// which only make sense for one run.
+    pub fn saved_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        let mut flag = |name: &str, value: Option<String>| match value {
+            Some(value) => flags.push(format!("{} {}", name, value)),
+            None => flags.push(name.to_string()),
+        };
+        let path = |path: &PathBuf| path.display().to_string();
+
+        flag("-w", Some(self.word_count.to_string()));
+        flag("-t", Some(self.file_extension.clone()));
+        flag("--min-words", Some(self.min_words.to_string()));
+        flag("--paste-threshold", Some(self.paste_threshold.to_string()));
+        flag("--tab-width", Some(self.tab_width.to_string()));
+        flag("--padding", Some(self.padding.to_string()));
+        flag("--max-files", Some(self.max_files.to_string()));
+        flag("--min-record-secs", Some(self.min_record_secs.to_string()));
+        flag("--rounds", Some(self.rounds.to_string()));
+        flag("--wpm-divisor", Some(self.wpm_divisor.to_string()));
+        flag("--debounce-ms", Some(self.debounce_ms.to_string()));
+        flag("--perfect-wpm", Some(self.perfect_wpm.to_string()));
+        flag(
+            "--edit-distance-limit",
+            Some(self.edit_distance_limit.to_string()),
+        );
+        flag(
+            "--result-pause",
+            Some(match self.result_pause {
+                ResultPause::Wait(pause) => pause.as_millis().to_string(),
+                ResultPause::UntilKey => "until-key".into(),
+            }),
+        );
+
+        // The palette by name, and the cursor colours given on top of it.
+        let palettes = [
+            ("default", Palette::DEFAULT),
+            ("colorblind", Palette::COLORBLIND),
+            ("high-contrast", Palette::HIGH_CONTRAST),
+        ];
+        let base = palettes.iter().find(|(_, palette)| {
+            Palette {
+                cursor: palette.cursor,
+                ..self.palette
+            } == *palette
+        });
+        if let Some((name, palette)) = base {
+            flag("--palette", Some(name.to_string()));
+            // A colour `-cf` and `-cb` can't name, like rgb, is left out
+            // and the cursor of the palette is used instead.
+            let changed = |color: Option<Color>, base: Option<Color>| {
+                color.filter(|_| color != base).and_then(color_arg)
+            };
+            if let Some(fg) = changed(self.palette.cursor.fg, palette.cursor.fg) {
+                flag("-cf", Some(fg));
+            }
+            if let Some(bg) = changed(self.palette.cursor.bg, palette.cursor.bg) {
+                flag("-cb", Some(bg));
+            }
+        }
+
+        let options = [
+            ("-ma", self.min_accuracy.map(|acc| acc.to_string())),
+            ("--name-regex", self.name_regex.clone()),
+            ("--max-chars", self.max_chars.map(|n| n.to_string())),
+            ("--log", self.log.as_ref().map(path)),
+            ("--markdown", self.markdown.as_ref().map(path)),
+            ("--record", self.record.as_ref().map(path)),
+            ("--tag", self.tag.clone()),
+            ("--lines", self.lines.map(|n| n.to_string())),
+            ("--offset", self.offset.map(|n| n.to_string())),
+            ("--finish-key", self.finish_key.map(String::from)),
+            ("--decimals", self.decimals.map(|n| n.to_string())),
+            ("--chars", self.chars.clone()),
+            ("--word-sep", self.word_sep.clone()),
+            (
+                "--idle-timeout",
+                self.idle_timeout.map(|t| t.as_secs().to_string()),
+            ),
+            ("--trail", self.trail.map(|n| n.to_string())),
+            ("--pace", self.pace.map(|wpm| wpm.to_string())),
+            // The daily challenge picks its own seed.
+            (
+                "--seed",
+                self.seed
+                    .filter(|_| self.daily.is_none())
+                    .map(|seed| seed.to_string()),
+            ),
+        ];
+        for (name, value) in options {
+            if value.is_some() {
+                flag(name, value);
+            }
+        }
+
+        for pattern in &self.include {
+            flag("--include", Some(pattern.clone()));
+        }
+        for pattern in &self.exclude {
+            flag("--exclude", Some(pattern.clone()));
+        }
+        for name in &self.file_names {
+            flag("--file-name", Some(name.clone()));
+        }
+        // `txt` and `md` are always prose.
+        for extension in self.prose_extensions.iter().skip(2) {
+            flag("--prose", Some(extension.clone()));
+        }
+
+        let switches = [
+            ("-s", self.strict),
+            ("-ss", self.skip_word_on_space),
+            ("--daily", self.daily.is_some()),
+            ("--show-whitespace", self.show_whitespace),
+            ("--share", self.share),
+            ("--copy", self.copy),
+            ("--confirm-quit", self.confirm_quit),
+            ("--exact-end", self.require_exact_end),
+            ("--percent", self.percent),
+            ("--keep-indent", self.keep_indent),
+            ("--no-units", self.no_units),
+            ("--menu", self.menu),
+            ("--live-wpm", self.live_wpm),
+            ("--git-diff", self.git_diff),
+            ("--printable-only", self.printable_only),
+            ("--scatter", self.scatter),
+            ("--ghost", self.ghost),
+            ("--symbols", self.symbols),
+            ("--shuffle", self.shuffle),
+            ("--caps", self.caps),
+            ("--strip-attributes", self.strip_attributes),
+            ("--legend", self.legend),
+            ("--verbatim", self.verbatim),
+            ("--strip-comments", self.strip_comments),
+            ("--weight-by-size", self.weight_by_size),
+        ];
+        for (name, on) in switches {
+            if on {
+                flag(name, None);
+            }
+        }
+
+        flags
+    }
+
+    /// The accuracy with `--decimals` or `decimals`, and a `%` unless `--no-units`.
+    pub fn accuracy(&self, accuracy: f32, decimals: usize) -> String {
+        let decimals = self.decimals.unwrap_or(decimals);
+        let unit = if self.no_units { "" } else { "%" };
+        format!("{:.*}{}", decimals, accuracy, unit)
     }
 }
 
+/// Where `--save-config` saves the flags:
+/// `$XDG_CONFIG_HOME/tccst/config`, or `~/.config/tccst/config`.
+pub fn saved_config_path() -> PathBuf {
+    let dir = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(shellexpand::tilde("~/.config").to_string()),
+    };
+    dir.join("tccst").join("config")
+}
+
+/// Save the `Config::saved_flags`, so they parse to the same options again.
+pub fn save_config(path: &Path, config: &Config) -> Result<()> {
+    let write = |e: std::io::Error| Error::Write(format!("{}: {}", path.display(), e));
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(write)?;
+    }
+
+    let mut text = config.saved_flags().join("\n");
+    text.push('\n');
+    std::fs::write(path, text).map_err(write)
+}
+
+/// The flags saved with `save_config`, none without a file.
+/// Lines that aren't a flag are skipped, a project path is never loaded.
+pub fn load_config(path: &Path) -> Result<Vec<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(text
+            .lines()
+            .filter(|line| line.starts_with('-'))
+            .map(String::from)
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(Error::Read(format!("{}: {}", path.display(), e))),
+    }
+}
+
+// The saved flags go right after the program name, leaving out the flags
+// given on the command line. So the command line replaces a saved flag,
+// every `--include` at once, rather than adding to it.
+fn with_saved(args: Vec<String>, saved: &[String]) -> Vec<String> {
+    let mut args = args.into_iter();
+    let program = args.next();
+    let args = args.collect::<Vec<_>>();
+
+    let saved = saved
+        .iter()
+        .map(|line| match line.split_once(' ') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (line.as_str(), None),
+        })
+        .filter(|(flag, _)| !args.iter().any(|arg| arg == flag))
+        .flat_map(|(flag, value)| std::iter::once(flag).chain(value))
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    program.into_iter().chain(saved).chain(args).collect()
+}
+
+// A colour as `-cf` and `-cb` take it, by name or ANSI value.
+fn color_arg(color: Color) -> Option<String> {
+    let name = match color {
+        Color::Black => "black",
+        Color::DarkGrey => "dark_grey",
+        Color::Red => "red",
+        Color::DarkRed => "dark_red",
+        Color::Green => "green",
+        Color::DarkGreen => "dark_green",
+        Color::Yellow => "yellow",
+        Color::DarkYellow => "dark_yellow",
+        Color::Blue => "blue",
+        Color::DarkBlue => "dark_blue",
+        Color::Magenta => "magenta",
+        Color::DarkMagenta => "dark_magenta",
+        Color::Cyan => "cyan",
+        Color::DarkCyan => "dark_cyan",
+        Color::White => "white",
+        Color::Grey => "grey",
+        Color::AnsiValue(value) => return Some(value.to_string()),
+        _ => return None,
+    };
+    Some(name.into())
+}
+
+// `.RS` to `rs`, file extensions are compared in lowercase.
+fn extension(arg: String) -> String {
+    arg.strip_prefix('.').unwrap_or(&arg).to_lowercase()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -123,15 +902,22 @@ mod test {
 
     #[test]
     fn parse_word_count() {
-        let args = "-w 12 /".split_whitespace().map(str::to_owned);
+        let args = "tccst -w 12 /".split_whitespace().map(str::to_owned);
         let config = Config::from_iter(args).unwrap();
         assert_eq!(config.word_count, 12);
     }
 
+    #[test]
+    fn parse_max_files() {
+        // No cap by default, a cap only keeps the first paths of the sorted walk.
+        assert_eq!(parse("tccst /").unwrap().max_files, 0);
+        assert_eq!(parse("tccst --max-files 50 /").unwrap().max_files, 50);
+    }
+
     #[test]
     fn parse_error() {
         // Missing path arg
-        let args = vec!["-s".to_string(), "-t".into(), "c".into()];
+        let args = vec!["tccst".to_string(), "-s".into(), "-t".into(), "c".into()];
         assert!(matches!(
             Config::from_iter(args.into_iter()),
             Err(PathMissing)
@@ -140,12 +926,317 @@ mod test {
 
     #[test]
     fn parse_extension() {
-        let args = "-p / -t .c".split_whitespace().map(str::to_owned);
+        let args = "tccst -p / -t .c".split_whitespace().map(str::to_owned);
         let config = Config::from_iter(args).unwrap();
         assert_eq!(config.file_extension, "c".to_string());
 
-        let args = "-p / ".split_whitespace().map(str::to_owned);
+        let args = "tccst -p / ".split_whitespace().map(str::to_owned);
         let config = Config::from_iter(args).unwrap();
         assert_eq!(config.file_extension, "rs".to_string());
     }
+
+    #[test]
+    fn parse_palette() {
+        let args = "tccst --palette colorblind /"
+            .split_whitespace()
+            .map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.palette, Palette::COLORBLIND);
+
+        let args = "tccst --palette high-contrast -cb red /"
+            .split_whitespace()
+            .map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.palette.cursor.bg, Some(Color::Red));
+        assert_eq!(config.palette.wrong, Palette::HIGH_CONTRAST.wrong);
+
+        let args = "tccst --palette nope /"
+            .split_whitespace()
+            .map(str::to_owned);
+        assert!(matches!(
+            Config::from_iter(args),
+            Err(Error::InvalidPalette)
+        ));
+    }
+
+    #[test]
+    fn parse_globs() {
+        let args = "tccst --include src/**/*.rs --include *.c --exclude target/** /"
+            .split_whitespace()
+            .map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.include, vec!["src/**/*.rs", "*.c"]);
+        assert_eq!(config.exclude, vec!["target/**"]);
+
+        let args = "tccst --include src/[a /"
+            .split_whitespace()
+            .map(str::to_owned);
+        assert!(matches!(
+            Config::from_iter(args),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn parse_name_regex() {
+        let args = "tccst --name-regex ^mod\\.rs$ /"
+            .split_whitespace()
+            .map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.name_regex.as_deref(), Some("^mod\\.rs$"));
+
+        let args = "tccst --name-regex (mod /"
+            .split_whitespace()
+            .map(str::to_owned);
+        assert_eq!(
+            Config::from_iter(args).unwrap_err(),
+            Error::InvalidArgument("invalid regex: (mod".into())
+        );
+    }
+
+    #[test]
+    fn parse_seed() {
+        let args = "tccst --seed 42 /".split_whitespace().map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.daily, None);
+
+        let args = "tccst --seed 42 --daily /"
+            .split_whitespace()
+            .map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        let today = daily::today();
+        assert_eq!(config.seed, Some(daily::seed(&today)));
+        assert_eq!(config.daily, Some(today));
+    }
+
+    #[test]
+    fn parse_log() {
+        let args = "tccst /".split_whitespace().map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.log, None);
+        assert_eq!(config.min_record_secs, 2.0);
+
+        let args = "tccst --log runs.tsv --min-record-secs 0.5 /"
+            .split_whitespace()
+            .map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.log, Some(PathBuf::from("runs.tsv")));
+        assert_eq!(config.min_record_secs, 0.5);
+    }
+
+    #[test]
+    fn parse_prose() {
+        let args = "tccst --prose .rst /".split_whitespace().map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert_eq!(config.prose_extensions, vec!["txt", "md", "rst"]);
+    }
+
+    #[test]
+    fn parse_projects() {
+        let args = "tccst -p ~/a b".split_whitespace().map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        let home = shellexpand::tilde("~/a").to_string();
+        assert_eq!(
+            config.project_paths,
+            vec![PathBuf::from(home), PathBuf::from("b")]
+        );
+    }
+
+    #[test]
+    fn parse_stats() {
+        // No project needed.
+        let args = "tccst --stats --log runs.tsv"
+            .split_whitespace()
+            .map(str::to_owned);
+        assert!(Config::from_iter(args).unwrap().stats);
+
+        let args = "tccst --stats /".split_whitespace().map(str::to_owned);
+        assert!(matches!(
+            Config::from_iter(args),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn parse_compare() {
+        // No project needed.
+        let config = parse("tccst --compare a.keys b.keys").unwrap();
+        assert_eq!(config.compare, Some(("a.keys".into(), "b.keys".into())));
+
+        assert!(matches!(
+            parse("tccst --compare a.keys"),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let config = parse("tccst --record run.keys /").unwrap();
+        assert_eq!(config.record, Some("run.keys".into()));
+        assert!(config
+            .saved_flags()
+            .contains(&"--record run.keys".to_string()));
+    }
+
+    #[test]
+    fn parse_finish_key() {
+        let args = "tccst --finish-key G /"
+            .split_whitespace()
+            .map(str::to_owned);
+        assert_eq!(Config::from_iter(args).unwrap().finish_key, Some('g'));
+
+        for key in ["c", "gg"] {
+            let args = format!("tccst --finish-key {} /", key);
+            assert!(matches!(
+                Config::from_iter(args.split_whitespace().map(str::to_owned)),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_result_pause() {
+        let config = |args: &str| {
+            let args = format!("tccst {} /", args);
+            Config::from_iter(args.split_whitespace().map(str::to_owned))
+        };
+
+        assert_eq!(config("").unwrap().result_pause, ResultPause::default());
+        assert_eq!(
+            config("--result-pause 1500").unwrap().result_pause,
+            ResultPause::Wait(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            config("--result-pause until-key").unwrap().result_pause,
+            ResultPause::UntilKey
+        );
+        assert!(config("--result-pause soon").is_err());
+    }
+
+    fn parse(args: &str) -> Result<Config> {
+        Config::from_iter(args.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn saved_config_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tccst").join("config");
+        let config = parse(
+            "tccst -w 5 -t py -ma 92.5 --palette colorblind -cf dark_red -cb 33 \
+             --include src/**/*.rs --include lib/** --exclude target/** --file-name Makefile \
+             --prose rst --idle-timeout 30 --result-pause until-key --finish-key g \
+             --word-sep _ --tag drill --pace 55.5 --seed 3 --caps --legend -ss /",
+        )
+        .unwrap();
+        save_config(&path, &config).unwrap();
+
+        let saved = load_config(&path).unwrap();
+        assert_eq!(saved, config.saved_flags());
+        let args = vec!["tccst".into(), "/".into()];
+        assert_eq!(
+            Config::from_iter(with_saved(args, &saved).into_iter()).unwrap(),
+            config
+        );
+        assert!(load_config(&dir.path().join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn saved_config_leaves_out_unnamed_colors() {
+        let mut config = parse("tccst -cb 33 /").unwrap();
+        config.palette.cursor.fg = Some(Color::Rgb { r: 1, g: 2, b: 3 });
+        let saved = config.saved_flags();
+        assert!(saved.contains(&"-cb 33".to_string()));
+        assert!(!saved.iter().any(|flag| flag.starts_with("-cf")));
+
+        let args = vec!["tccst".into(), "/".into()];
+        let loaded = Config::from_iter(with_saved(args, &saved).into_iter()).unwrap();
+        assert_eq!(loaded.palette.cursor.fg, Palette::DEFAULT.cursor.fg);
+        assert_eq!(loaded.palette.cursor.bg, Some(Color::AnsiValue(33)));
+    }
+
+    #[test]
+    fn saved_config_leaves_out_single_runs() {
+        let config = parse(
+            "tccst --dry-run --log runs.tsv --stats --words-file w.txt --compare a.keys b.keys /x",
+        )
+        .unwrap();
+        let saved = config.saved_flags();
+        assert!(saved.contains(&"--log runs.tsv".to_string()));
+        for arg in ["--dry-run", "--stats", "--words-file", "--compare", "/x"] {
+            assert!(!saved.iter().any(|flag| flag.contains(arg)), "{}", arg);
+        }
+    }
+
+    #[test]
+    fn command_line_overrides_saved_config() {
+        let saved = parse("tccst -w 5 --share --include a/** --include b/** /saved")
+            .unwrap()
+            .saved_flags();
+        let args = "tccst -w 7 --include c/** /"
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect();
+        let config = Config::from_iter(with_saved(args, &saved).into_iter()).unwrap();
+        assert_eq!(config.word_count, 7);
+        assert!(config.share);
+        assert_eq!(config.include, vec!["c/**"]);
+        assert_eq!(config.project_paths, vec![PathBuf::from("/")]);
+    }
+
+    #[test]
+    fn save_only_valid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        let args = |args: &str| args.split_whitespace().map(str::to_owned).collect();
+
+        assert!(Config::with_config_file(args("tccst --save-config -w 0 /"), &path).is_err());
+        assert!(Config::with_config_file(args("tccst --save-config -v"), &path).is_err());
+        assert!(!path.exists());
+
+        Config::with_config_file(args("tccst --save-config -w 3 --share /"), &path).unwrap();
+        let config = Config::with_config_file(args("tccst /"), &path).unwrap();
+        assert_eq!((config.word_count, config.share), (3, true));
+        let config = Config::with_config_file(args("tccst --no-config /"), &path).unwrap();
+        assert_eq!((config.word_count, config.share), (10, false));
+
+        // Saving the defaults clears the saved flags.
+        Config::with_config_file(args("tccst --save-config /"), &path).unwrap();
+        assert!(
+            !Config::with_config_file(args("tccst /"), &path)
+                .unwrap()
+                .share
+        );
+    }
+
+    #[test]
+    fn parse_chars() {
+        assert_eq!(
+            parse("tccst --chars asdf /").unwrap().chars.as_deref(),
+            Some("asdf")
+        );
+        assert!(matches!(
+            parse("tccst --chars asdf --caps /"),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn parse_lines() {
+        let args = "tccst --lines 3 --caps /"
+            .split_whitespace()
+            .map(str::to_owned);
+        assert_eq!(Config::from_iter(args).unwrap().lines, Some(3));
+
+        for flag in [
+            "--shuffle",
+            "--scatter",
+            "--symbols",
+            "--min-words 5",
+            "--offset 2",
+        ] {
+            let args = format!("tccst --lines 3 {} /", flag);
+            assert!(matches!(
+                Config::from_iter(args.split_whitespace().map(str::to_owned)),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+    }
 }
@@ -0,0 +1,64 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Today's date (UTC) as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    date(secs)
+}
+
+/// The date (UTC) of the unix timestamp as `YYYY-MM-DD`.
+pub fn date(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / SECS_PER_DAY) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Seed for the daily challenge.
+/// This is FNV-1a rather than the std hasher, so the seed
+/// is the same no matter what version of Rust tccst was built with.
+pub fn seed(date: &str) -> u64 {
+    date.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Days since 1970-01-01 to (year, month, day).
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11016 * SECS_PER_DAY), "2000-02-29");
+        assert_eq!(date(20741 * SECS_PER_DAY), "2026-10-15");
+    }
+
+    #[test]
+    fn seed_changes_at_midnight() {
+        let midnight = 20741 * SECS_PER_DAY;
+        assert_eq!(
+            seed(&date(midnight)),
+            seed(&date(midnight + SECS_PER_DAY - 1))
+        );
+        assert_ne!(seed(&date(midnight - 1)), seed(&date(midnight)));
+    }
+}
@@ -1,36 +1,156 @@
+use std::fmt;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     PathMissing,
     NoFiles,
-    InsufficientWords,
+    /// The word count, and the most words found in any single file.
+    InsufficientWords {
+        requested: usize,
+        available: usize,
+    },
+    /// The `--lines` asked for, and the most non-empty lines in any single file.
+    InsufficientLines {
+        requested: usize,
+        available: usize,
+    },
     ZeroWordCount,
     NeedsHelp,
     InvalidColor,
+    InvalidPalette,
     InvalidFile,
+    InvalidArgument(String),
+    Write(String),
+    Read(String),
+    /// The two runs of `--compare` typed different words.
+    ReplayMismatch,
     Version,
 }
 
-impl Error {
-    pub fn to_string(self) -> String {
-        match self {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg: String = match self {
             Error::PathMissing => "Provide a path to a Rust project".into(),
             Error::InvalidColor => "Color needs to be a u8 or a color string.".into(),
+            Error::InvalidPalette => "Palette needs to be one of: default, colorblind, high-contrast".into(),
             Error::InvalidFile => "File format was incorrect (possibly binary?)".into(),
+            Error::InvalidArgument(msg) => format!("Invalid argument: {}", msg),
+            Error::Write(msg) => format!("Failed to write {}", msg),
+            Error::Read(msg) => format!("Failed to read {}", msg),
+            Error::ReplayMismatch => "The runs given to --compare typed different words, record both with the same --seed".into(),
             Error::NoFiles => "No code files found".into(),
-            Error::InsufficientWords => "Not enough words to meet word count".into(),
+            Error::InsufficientWords {
+                requested,
+                available,
+            } => format!(
+                "Not enough words to meet word count: {} requested, but the most in a single file is {}. Try a lower -w",
+                requested, available
+            ),
+            Error::InsufficientLines {
+                requested,
+                available,
+            } => format!(
+                "Not enough lines: {} requested, but the most in a single file is {}. Try a lower --lines",
+                requested, available
+            ),
             Error::ZeroWordCount => "Word count can not be zero".into(),
-            Error::Version => format!("Version: {}", env!("CARGO_PKG_VERSION")),
-            Error::NeedsHelp => "Usage: tccst -t rs -w 5 path_to_project
+            // All a bug report needs to know about the build.
+            Error::Version => format!(
+                "{} {} ({}-{})",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION"),
+                std::env::consts::ARCH,
+                std::env::consts::OS
+            ),
+            Error::NeedsHelp => "Usage: tccst -t rs -w 5 path_to_project [other_projects...]
     -t : extension of files to use for words. Defaults to rs for Rust.
     -w : number of words to type against. Defaults to 10.
     -ma: minimum accuracy required to see the actual result.
-    -s : strict mode. Input must be matched perfectly, otherwise game can't end! Space will not skip the entire word
+    -s : strict mode. Wrong keys are rejected and the cursor only moves on the right key. Space will not skip the entire word
     -ss: skip word on space, unless it's the first character.
     -cf: set cursor foreground colour.
     -cb: set cursor background colour.
-    -v: version info.".into(),
-        }
+    --paste-threshold: keystrokes within 20ms to flag a run as pasted. Defaults to 5, 0 disables.
+    --tab-width: number of spaces a tab expands to. Defaults to 4.
+    --max-files: stop looking for files after finding this many, the first ones by name. Defaults to 0, no limit.
+    --include: only use files matching this glob, can be repeated. Replaces -t.
+    --exclude: never use files matching this glob, can be repeated.
+    --name-regex: only use files with a file name matching this regex.
+    --max-chars: only use the first N characters of a file. Defaults to the terminal area, 80x24 with a seed.
+    --show-whitespace: draw spaces as a dim dot.
+    --share: print a one line summary of the last result on exit.
+    --seed: seed for the word selection, the same seed selects the same words.
+    --daily: daily challenge, everyone gets the same words today.
+    --log: append every finished run to this file.
+    --min-record-secs: runs shorter than this are not logged. Defaults to 2.
+    --rounds: play this many games in a row and show the average at the end.
+    --prose: files with this extension are prose, comments are not stripped. txt and md already are.
+    --symbols: prefer words with digits or symbols in them.
+    --words-file: pick the words from this file instead of the project.
+    --shuffle: shuffle the selected words.
+    --pace: race a pacer typing at this wpm.
+    --caps: capitalize the first letter of random words.
+    --strip-attributes: leave out lines with Rust attributes like #[derive(Debug)].
+    --wpm-divisor: characters per word when converting cpm to wpm. Defaults to 5.
+    --min-words: never select fewer words than this. Defaults to 1.
+    --legend: show what the colours mean while typing.
+    --verbatim: type whole lines, comments included. -w is the number of lines.
+    --strip-comments: leave out comments in verbatim mode.
+    --weight-by-size: pick larger files more often, instead of every file as often.
+    --padding: columns and rows to leave empty around the edges. Defaults to 0.
+    --markdown: write the result of the last run above -ma to this file as a markdown table.
+    --stats: show the average and best results in the --log file for every extension, and quit.
+    --tag: label every logged run with this, --stats then only counts the runs with this tag.
+    --lines: type every word of this many consecutive lines of a file, instead of -w words, in order.
+    --copy: copy the --share summary of every finished game to the clipboard.
+    --offset: start at this word of the file instead of a random one, 0 is the first word.
+    --confirm-quit: ask before Ctrl-C quits a running game.
+    --exact-end: only finish once the text is typed exactly, not on a space after the last word.
+    --file-name: also use files with exactly this name, like Makefile. Can be repeated.
+    --percent: show how much of the text is typed in the top left corner.
+    --dry-run: print the selected words and quit, e.g. to see what a --seed selects.
+    --finish-key: Ctrl and this key finish a running game, like Esc. A way out of strict mode.
+    --keep-indent: type the indentation of every line too, with --verbatim or --lines.
+    --decimals <n>: decimals of the accuracy in the results and exports.
+    --no-units: leave the labels and the % out of the results.
+    --menu: n on the results screen goes back to the start screen instead of quitting.
+    --live-wpm: show the wpm of the last 10 seconds on the right, below the project name.
+    --git-diff: only use the lines added in the uncommitted changes of the project.
+    --result-pause: ms the results of a round stay up with --rounds, or until-key. Defaults to 0.
+    --debounce-ms: ignore the same key again within this many ms, for stuck keys. Defaults to 0, off.
+    --printable-only: take control characters and invisible spaces out of the words.
+    --chars: only use words made of nothing but these characters, e.g. asdfjkl;
+    --idle-timeout: pause the clock after this many seconds without a key, until the next key.
+    --scatter: pick the words from anywhere in the file instead of consecutive words.
+    --edit-distance-limit: no accuracy by edit distance for texts longer than this. Defaults to 5000.
+    --trail: fade the background of this many typed cells before the cursor.
+    --files-from: only use the files listed in this file, one per line. No project path needed.
+    --filter-listed: only use the listed files that pass the file filters.
+    --ghost: retrying the same words with r races a cursor typing like the last run.
+    --record: write the keystrokes of every finished run to this file, for --compare.
+    --compare <a> <b>: play back two --record files of the same words side by side, and quit.
+    --word-sep: more chars that split words, like spaces, e.g. _ for snake_case.
+    --perfect-wpm: the wpm a run without mistakes needs to be a perfect run. Defaults to 0.
+    --save-config: save the options of this run, every later run starts with them.
+    --no-config: leave out the options saved with --save-config.
+    --palette: colour palette: default, colorblind or high-contrast.
+    -v, --version: name, version and build target, for bug reports.".into(),
+        };
+
+        write!(f, "{}", msg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn version() {
+        let version = Error::Version.to_string();
+        assert!(version.starts_with(&format!("tccst {} (", env!("CARGO_PKG_VERSION"))));
+        assert!(version.contains(std::env::consts::OS));
     }
 }
@@ -1,5 +1,22 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 
+use crate::config::Config;
+use crate::error::{Error, Result};
+
+// Keystrokes arriving faster than this are assumed to be pasted,
+// nobody types `paste_threshold` characters inside of this window.
+const PASTE_WINDOW: Duration = Duration::from_millis(20);
+
+// Number of consecutive keystrokes the peak wpm is measured over,
+// a single fast keystroke says nothing.
+const PEAK_KEYS: usize = 10;
+
+// How often the wpm is sampled while the game is running.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(PartialEq)]
 pub enum GameState {
     Stopped,
@@ -11,79 +28,380 @@ pub enum GameState {
         word_count: usize,
         mistakes: usize,
         accuracy: f32,
+        suspected_paste: bool,
+        word_speeds: Vec<WordSpeed>,
+        /// From the start of the game to the first keystroke.
+        time_to_first: Duration,
+        /// Highest wpm over `PEAK_KEYS` consecutive keystrokes.
+        peak_wpm: usize,
+        /// Every keystroke per minute, including backspaces and wrong keys.
+        kspm: usize,
+        /// The mistakes by kind.
+        errors: Errors,
+        /// The longest pause between two keystrokes, and the key after it.
+        slowest_key: Option<(char, Duration)>,
+        /// Chars of the text never typed, if the game was finished early.
+        remaining: usize,
+        reason: FinishReason,
+        /// Accuracy from the edit distance between the input and the typed
+        /// part of the text. A missing or extra char costs one, where
+        /// `accuracy` counts every char after it as a mistake.
+        edit_accuracy: f32,
+        /// Words skipped with `skip_word_on_space`. The chars of a skipped
+        /// word are in the `mistakes` too.
+        skipped_words: usize,
+        /// Completed without a single mistake, at `perfect_wpm` or faster.
+        perfect: bool,
     },
 }
 
+/// How a game finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinishReason {
+    /// The end of the text was reached.
+    Completed,
+    /// Finished early with `finish`, like Esc does.
+    Aborted,
+}
+
+impl fmt::Display for FinishReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FinishReason::Completed => write!(f, "completed"),
+            FinishReason::Aborted => write!(f, "aborted"),
+        }
+    }
+}
+
+impl FromStr for FinishReason {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "completed" => Ok(FinishReason::Completed),
+            "aborted" => Ok(FinishReason::Aborted),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown finish reason: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Mistakes by kind, the same kinds that have their own colour while typing.
+/// Together they add up to the mistakes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Errors {
+    /// A space typed over a non-space character, including the characters
+    /// of words skipped with `skip_word_on_space`.
+    pub missed: usize,
+    /// A character typed over a space, or past the end of the text.
+    pub extra: usize,
+    /// A wrong character typed over a non-space character.
+    pub wrong: usize,
+}
+
+impl Errors {
+    // Count the wrong key `c` typed where `expected` was.
+    fn count(&mut self, c: char, expected: Option<char>) {
+        match expected {
+            Some(' ') | None => self.extra += 1,
+            Some(_) if c == ' ' => self.missed += 1,
+            Some(_) => self.wrong += 1,
+        }
+    }
+}
+
+/// Typing speed of a single word in the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordSpeed {
+    pub word: String,
+    /// `None` if the word was skipped.
+    pub wpm: Option<f32>,
+}
+
+/// Characters per word when converting cpm to wpm.
+/// The average word length in English is 4.7 characters, so we are using 5.
+pub const DEFAULT_WPM_DIVISOR: f32 = 5.0;
+
+/// Texts longer than this many chars get the `accuracy` as the `edit_accuracy`.
+/// The edit distance takes time in proportion to the square of the length
+/// (memory only grows with the length), which would hold up the results of a long text.
+pub const DEFAULT_EDIT_DISTANCE_LIMIT: usize = 5000;
+
+/// Options that change how the game is played.
+#[derive(Debug, Clone, Copy)]
+pub struct GameOptions {
+    pub strict: bool,
+    pub skip_word_on_space: bool,
+    pub paste_threshold: usize,
+    pub wpm_divisor: f32,
+    /// Only finish once the input is exactly the text, not on a space
+    /// after the last word. Unlike `strict`, wrong keys are not rejected.
+    pub require_exact_end: bool,
+    /// Pause the clock when no key comes for this long, until the next key.
+    pub idle_timeout: Option<Duration>,
+    /// See `DEFAULT_EDIT_DISTANCE_LIMIT`.
+    pub edit_distance_limit: usize,
+    /// The wpm a run without mistakes needs to be `perfect`.
+    pub perfect_wpm: usize,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            skip_word_on_space: false,
+            paste_threshold: 0,
+            wpm_divisor: DEFAULT_WPM_DIVISOR,
+            require_exact_end: false,
+            idle_timeout: None,
+            edit_distance_limit: DEFAULT_EDIT_DISTANCE_LIMIT,
+            perfect_wpm: 0,
+        }
+    }
+}
+
+impl From<&Config> for GameOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            strict: config.strict,
+            skip_word_on_space: config.skip_word_on_space,
+            paste_threshold: config.paste_threshold,
+            wpm_divisor: config.wpm_divisor,
+            require_exact_end: config.require_exact_end,
+            idle_timeout: config.idle_timeout,
+            edit_distance_limit: config.edit_distance_limit,
+            perfect_wpm: config.perfect_wpm,
+        }
+    }
+}
+
 pub struct Game {
     pub text: String,
     pub text_chars: Vec<char>,
     pub state: GameState,
     input: String,
+    // Every input char, and whether it matches the text.
+    // Kept in step with `input` by `push_input` and `pop_input`.
+    checked: Vec<(char, bool)>,
     mistakes: usize,
     word_count: usize,
-    strict: bool,
-    skip_word_on_space: bool,
+    options: GameOptions,
+    key_times: Vec<Instant>,
+    // The char of every push, in step with `key_times`.
+    key_chars: Vec<char>,
+    // Time of the last keystroke on each index of the text.
+    index_times: Vec<Option<Instant>>,
+    wpm_samples: Vec<f32>,
+    // When the game was started, and how long it took from there
+    // to the first keystroke.
+    started: Instant,
+    time_to_first: Option<Duration>,
+    // Every push and pop since the clock started.
+    keystrokes: usize,
+    // The last key was rejected in strict mode.
+    blocked: bool,
+    // Any key, including backspace, for the `idle_timeout`.
+    last_key: Option<Instant>,
+    paused: bool,
+    errors: Errors,
+    // The chars of the words skipped with `skip_word_on_space`,
+    // kept in step with `checked` by `pop_input`.
+    skipped: Vec<Range<usize>>,
+    // Every skip, like `mistakes` it's not undone by deleting the padding.
+    skipped_words: usize,
+    // When each char was typed in an earlier run of the same text,
+    // since its first key, for `ghost_index`.
+    ghost: Vec<Duration>,
 }
 
 impl Game {
-    pub fn new(words: &[String], strict: bool, skip_word_on_space: bool) -> Self {
+    /// Fails with `Error::ZeroWordCount` if there is nothing to type,
+    /// as a game without text can't be finished or scored.
+    pub fn new(words: &[String], options: GameOptions) -> Result<Self> {
         let word_count = words.len();
         let text = words.join(" ");
         let text_chars = text.chars().collect::<Vec<_>>();
+        if text.trim().is_empty() {
+            return Err(Error::ZeroWordCount);
+        }
 
-        Self {
+        let inst = Self {
             word_count,
             input: String::new(),
+            checked: Vec::new(),
             text,
-            text_chars,
             mistakes: 0,
             state: GameState::Running(Instant::now()),
-            strict,
-            skip_word_on_space,
-        }
+            options,
+            key_times: Vec::new(),
+            key_chars: Vec::new(),
+            index_times: vec![None; text_chars.len()],
+            wpm_samples: Vec::new(),
+            started: Instant::now(),
+            time_to_first: None,
+            keystrokes: 0,
+            blocked: false,
+            errors: Errors::default(),
+            skipped: Vec::new(),
+            skipped_words: 0,
+            last_key: None,
+            paused: false,
+            ghost: Vec::new(),
+            text_chars,
+        };
+
+        Ok(inst)
     }
 
     fn wpm(&self, dur: Duration) -> f32 {
-        // the average word length in English is 4.7 characters, so we are using 5
         // ideally we would also compare this to collected correct characters to provide additional normalize results
         // ((self.text.chars().count() as f32 * (60.0 / dur.as_secs_f32())) / 5.0) as usize
-        self.cpm(dur) / 5.0
+        self.cpm(dur) / self.options.wpm_divisor
     }
 
+    // Based on the typed chars rather than the whole text,
+    // so a game finished early doesn't count the untyped rest.
     fn cpm(&self, dur: Duration) -> f32 {
-        self.text.chars().count() as f32 * (60.0 / dur.as_secs_f32())
+        self.checked.len() as f32 * (60.0 / dur.as_secs_f32())
     }
 
-    pub fn input(&self) -> Vec<(char, bool)> {
-        let input = self.input.chars().collect::<Vec<_>>();
-        let text = self.text_chars.iter().take(input.len());
+    // True if `paste_threshold` keystrokes arrived within the `PASTE_WINDOW`.
+    // A threshold of zero disables the check.
+    fn suspected_paste(&self) -> bool {
+        if self.options.paste_threshold < 2 {
+            return false;
+        }
 
-        input
-            .into_iter()
-            .zip(text)
-            .map(|(i, t)| (i, i == *t))
-            .collect()
+        self.key_times
+            .windows(self.options.paste_threshold)
+            .any(|w| w[w.len() - 1].duration_since(w[0]) < PASTE_WINDOW)
+    }
+
+    /// True if strict mode rejected the last key,
+    /// until the right key is pressed or a key is deleted.
+    pub fn blocked(&self) -> bool {
+        self.blocked
+    }
+
+    /// The chars of the text that were skipped with `skip_word_on_space`.
+    /// The space after a skipped word is not part of the range.
+    pub fn skipped(&self) -> &[Range<usize>] {
+        &self.skipped
+    }
+
+    /// How many times a word was skipped with `skip_word_on_space`.
+    pub fn skipped_words(&self) -> usize {
+        self.skipped_words
+    }
+
+    pub fn input(&self) -> &[(char, bool)] {
+        &self.checked
+    }
+
+    /// The chars of the text the input covers, right or wrong.
+    pub fn typed(&self) -> &[char] {
+        &self.text_chars[..self.typed_len()]
+    }
+
+    /// The chars of the text left to type.
+    pub fn remaining(&self) -> &[char] {
+        &self.text_chars[self.typed_len()..]
+    }
+
+    // The input can't run past the end of the text, but a clamp costs nothing.
+    fn typed_len(&self) -> usize {
+        self.checked.len().min(self.text_chars.len())
+    }
+
+    fn push_input(&mut self, c: char) {
+        let correct = self.text_chars.get(self.checked.len()) == Some(&c);
+        self.checked.push((c, correct));
+        self.input.push(c);
+    }
+
+    fn pop_input(&mut self) -> Option<char> {
+        self.checked.pop();
+        let len = self.checked.len();
+        self.skipped.retain(|range| range.end <= len);
+        self.input.pop()
+    }
+
+    /// True if the clock is paused after the `idle_timeout`, until the next key.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Every key resumes a paused game. The time the clock was paused doesn't
+    // count, the clock starts that much later instead, and every key so far was
+    // typed that much later, for the slowest key, the word speeds and the ghost.
+    // The `idle_timeout` before the pause still counts: the key before the pause
+    // and the one after it are that far apart, not at the same time, which would
+    // look pasted and make a peak.
+    fn wake(&mut self, now: Instant) {
+        if let (true, GameState::Running(start), Some(last)) =
+            (self.paused, &mut self.state, self.last_key)
+        {
+            let timeout = self.options.idle_timeout.unwrap_or_default();
+            let idle = now.saturating_duration_since(last).saturating_sub(timeout);
+            *start += idle;
+            self.key_times.iter_mut().for_each(|t| *t += idle);
+            self.index_times
+                .iter_mut()
+                .flatten()
+                .for_each(|t| *t += idle);
+        }
+
+        self.paused = false;
+        self.last_key = Some(now);
     }
 
     pub fn push(&mut self, c: char) {
-        if self.input.len() == 0 {
+        let now = Instant::now();
+        self.wake(now);
+
+        // Not reset when the input is cleared, that is still the same game.
+        if self.time_to_first.is_none() {
+            self.time_to_first = Some(self.started.elapsed());
+        }
+
+        // The first key, or the first after clearing the input, starts over.
+        // Not after a wrong key rejected in strict mode, that leaves the input
+        // empty too, but is part of the game and counts as a mistake.
+        if self.input.is_empty() && !self.blocked {
             self.state = GameState::Running(Instant::now());
             self.mistakes = 0;
+            self.errors = Errors::default();
+            self.key_times.clear();
+            self.key_chars.clear();
+            self.index_times.iter_mut().for_each(|t| *t = None);
+            self.wpm_samples.clear();
+            self.keystrokes = 0;
         }
-        let current_index = self.input.len();
-        let next_index = current_index + 1;
+        self.keystrokes += 1;
+        self.key_times.push(now);
+        self.key_chars.push(c);
+        let current_index = self.checked.len();
 
         // If skip_word_on_space: Skip the entire word if space was pressed anywhere
         // but on the first character of the word, or as the absolute
         // first input.
-        match (self.skip_word_on_space, c, self.text.chars().skip(current_index).next()) {
+        match (
+            self.options.skip_word_on_space,
+            c,
+            self.text.chars().nth(current_index),
+        ) {
             (false, ..) => {}
             // If space is pressed and current char is not a space,
             // and there is some player input, we advance the cursor
-            // to the next word and count skipped chars as mistakes.
+            // to the next word. The rest of the word and the space after it
+            // are padded with spaces, each one a missed char in `mistakes`,
+            // and the word counts once in `skipped_words`. The padding is drawn
+            // as skipped, not as missed, and is deleted at once by `pop`.
             (true, ' ', Some(current)) if current != ' ' && current_index > 0 => {
                 // Don't advance if the cursor is at the beginning of a word
-                match self.text.chars().skip(current_index - 1).next() {
+                match self.text.chars().nth(current_index - 1) {
                     None | Some(' ') => return,
                     Some(_) => (),
                 };
@@ -96,11 +414,19 @@ impl Game {
                     .count()
                     + 1; // + 1 for the initial space character.
 
-                (0..mistakes).for_each(|_| self.input.push(' '));
+                (0..mistakes).for_each(|_| self.push_input(' '));
+                self.skipped
+                    .push(current_index..current_index + mistakes - 1);
+                // The space press lands on the space after the skipped word.
+                if let Some(time) = self.index_times.get_mut(current_index + mistakes - 1) {
+                    *time = Some(now);
+                }
                 self.mistakes += mistakes;
+                self.errors.missed += mistakes;
+                self.skipped_words += 1;
 
-                if !self.strict && self.input.len() >= self.text.len() {
-                    self.finish();
+                if self.space_finishes() && self.checked.len() >= self.text_chars.len() {
+                    self.end(FinishReason::Completed);
                 }
 
                 return;
@@ -109,61 +435,243 @@ impl Game {
             _ => (),
         };
 
-        self.input.push(c);
+        // In strict mode the cursor only moves on the right key.
+        if self.options.strict {
+            self.blocked = self.text_chars.get(current_index) != Some(&c);
+            if self.blocked {
+                self.mistakes += 1;
+                self.errors
+                    .count(c, self.text_chars.get(current_index).copied());
+                return;
+            }
+        }
 
-        let b = self.text.chars().take(next_index).last();
+        // Keys past the end of the text are never part of the input.
+        // Without strict mode (which rejected it above) or `require_exact_end`
+        // a space there finishes the game. Anything else is an extra char,
+        // counted once as a mistake and dropped.
+        let expected = match self.text_chars.get(current_index) {
+            Some(&expected) => expected,
+            None if c == ' ' && self.space_finishes() => {
+                self.end(FinishReason::Completed);
+                return;
+            }
+            None => {
+                self.mistakes += 1;
+                self.errors.count(c, None);
+                return;
+            }
+        };
 
-        // if we have mistyped and press space after the last word
-        // quit the game
-        let should_quit = !self.strict && next_index >= self.text.len() + 1 && c == ' ';
+        self.push_input(c);
+        if let Some(time) = self.index_times.get_mut(current_index) {
+            *time = Some(now);
+        }
 
-        if !should_quit && Some(c) != b {
+        if c != expected {
             self.mistakes += 1;
+            self.errors.count(c, Some(expected));
         }
 
-        // if we input the text correctly or we press space after the last word
-        if self.input == self.text || should_quit {
-            self.finish();
+        if self.checked.len() == self.text_chars.len() && self.input == self.text {
+            self.end(FinishReason::Completed);
         }
+    }
 
-        if self.input.len() > self.text.len() {
-            self.input.pop();
+    /// Push every char of `s`, as if typed, until the game is finished.
+    /// The rest of `s` is ignored once it is.
+    pub fn feed(&mut self, s: &str) {
+        for c in s.chars() {
+            if self.is_finished() {
+                break;
+            }
+            self.push(c);
         }
     }
 
+    // A space after the last word finishes the game, right or wrong.
+    fn space_finishes(&self) -> bool {
+        !self.options.strict && !self.options.require_exact_end
+    }
+
     pub fn pop(&mut self) {
+        self.wake(Instant::now());
+        self.keystrokes += 1;
+        self.blocked = false;
         match self.input.chars().last() {
             Some(' ') => {
                 while let Some(' ') = self.input.chars().last() {
-                    self.input.pop();
+                    self.pop_input();
                 }
             }
-            _ => drop(self.input.pop()),
+            _ => drop(self.pop_input()),
         }
     }
 
     pub fn pop_word(&mut self) {
-        let to_remove = self.input.len() - self
-            .input
-            .chars()
-            .rev()
-            .skip_while(|&c| c == ' ') // remove until non-whitespace is found
-            .skip_while(|&c| c != ' ') // remove until whitespace is found
-            .count();
-        (0..to_remove).for_each(|_| drop(self.input.pop()));
+        self.wake(Instant::now());
+        self.keystrokes += 1;
+        self.blocked = false;
+        if self.input.is_empty() {
+            return;
+        }
+
+        let to_remove = self.input.chars().count()
+            - self
+                .input
+                .chars()
+                .rev()
+                .skip_while(|&c| c == ' ') // remove until non-whitespace is found
+                .skip_while(|&c| c != ' ') // remove until whitespace is found
+                .count();
+        (0..to_remove).for_each(|_| {
+            self.pop_input();
+        });
+    }
+
+    /// Sample the wpm (based on correctly typed characters) once every
+    /// `SAMPLE_INTERVAL` since the first keystroke.
+    pub fn tick(&mut self, now: Instant) {
+        let start = match self.state {
+            GameState::Running(start) if !self.key_times.is_empty() => start,
+            _ => return,
+        };
+
+        if let (Some(timeout), Some(last)) = (self.options.idle_timeout, self.last_key) {
+            if now.saturating_duration_since(last) > timeout {
+                self.paused = true;
+            }
+        }
+
+        // Nothing is typed while paused, there is nothing to sample.
+        if self.paused {
+            return;
+        }
+
+        let samples = (now.saturating_duration_since(start).as_secs_f32()
+            / SAMPLE_INTERVAL.as_secs_f32()) as usize;
+
+        while self.wpm_samples.len() < samples {
+            let correct = self.input().iter().filter(|(_, correct)| *correct).count();
+            let minutes =
+                (self.wpm_samples.len() + 1) as f32 * SAMPLE_INTERVAL.as_secs_f32() / 60.0;
+            self.wpm_samples
+                .push(correct as f32 / self.options.wpm_divisor / minutes);
+        }
+    }
+
+    /// The wpm sampled every second during the game.
+    pub fn wpm_samples(&self) -> &[f32] {
+        &self.wpm_samples
+    }
+
+    /// The wpm over the keystrokes of the last `window`, or since the first
+    /// keystroke if that is more recent. Unlike the wpm of the whole game it
+    /// follows speeding up or slowing down right away.
+    pub fn windowed_wpm(&self, window: Duration) -> f32 {
+        windowed_wpm(
+            &self.key_times,
+            window,
+            Instant::now(),
+            self.options.wpm_divisor,
+        )
+    }
+
+    /// Index in the text of a pacer typing at `wpm` since the first keystroke.
+    pub fn pacer_index(&self, wpm: f32, now: Instant) -> Option<usize> {
+        match self.state {
+            GameState::Running(start) if !self.key_times.is_empty() => {
+                let minutes = now.saturating_duration_since(start).as_secs_f32() / 60.0;
+                let chars = minutes * wpm * self.options.wpm_divisor;
+                Some((chars as usize).min(self.text_chars.len()))
+            }
+            _ => None,
+        }
+    }
+
+    /// When each char of the text was typed, since the first keystroke,
+    /// up to the first char that wasn't typed.
+    pub fn typing_times(&self) -> Vec<Duration> {
+        let first = match self.key_times.first() {
+            Some(&first) => first,
+            None => return Vec::new(),
+        };
+        self.index_times
+            .iter()
+            .map_while(|time| time.map(|t| t.saturating_duration_since(first)))
+            .collect()
+    }
+
+    /// Race the `typing_times` of an earlier run of the same text.
+    pub fn set_ghost(&mut self, times: Vec<Duration>) {
+        self.ghost = times;
+    }
+
+    /// Index in the text the ghost had typed to at the same time in its run,
+    /// since the first keystroke.
+    pub fn ghost_index(&self, now: Instant) -> Option<usize> {
+        match self.state {
+            GameState::Running(start) if !self.key_times.is_empty() && !self.ghost.is_empty() => {
+                let elapsed = now.saturating_duration_since(start);
+                Some(self.ghost.iter().take_while(|&&t| t <= elapsed).count())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        matches!(self.state, GameState::Stopped)
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, GameState::Running(_))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, GameState::Finished { .. })
     }
 
     pub fn start(&mut self) {
         self.state = GameState::Running(Instant::now());
+        self.started = Instant::now();
+        self.time_to_first = None;
+    }
+
+    /// Back to waiting for a key to start.
+    pub fn stop(&mut self) {
+        self.state = GameState::Stopped;
+    }
+
+    // Falls back to the `accuracy` past the `edit_distance_limit`.
+    fn edit_accuracy(&self, accuracy: f32) -> f32 {
+        let typed = self.typed();
+        if typed.len() > self.options.edit_distance_limit {
+            return accuracy;
+        }
+
+        let input = self.input.chars().collect::<Vec<_>>();
+        let distance = edit_distance(&input, typed) as f32;
+        (100.0 - distance / typed.len().max(1) as f32 * 100.0).max(0.0)
     }
 
+    /// Finish early, with the results so far.
     pub fn finish(&mut self) {
+        self.end(FinishReason::Aborted);
+    }
+
+    fn end(&mut self, reason: FinishReason) {
         match self.state {
             GameState::Stopped | GameState::Finished { .. } => (),
             GameState::Running(now) => {
-                let elapsed = now.elapsed();
+                // Paused, the clock stopped the `idle_timeout` after the last key.
+                let elapsed = match (self.paused, self.last_key) {
+                    (true, Some(last)) => (last + self.options.idle_timeout.unwrap_or_default())
+                        .saturating_duration_since(now),
+                    _ => now.elapsed(),
+                };
                 let mistakes = self.mistakes as f32;
-                let char_count = self.text_chars.len() as f32;
+                // All of the text, unless the game was finished early.
+                let char_count = self.checked.len().max(1) as f32;
 
                 let accuracy = {
                     let a = 100.0 - (mistakes / char_count) * 100.0;
@@ -173,41 +681,194 @@ impl Game {
                         a
                     }
                 };
+                let wpm = self.wpm(elapsed) as usize;
                 self.state = GameState::Finished {
                     elapsed,
-                    wpm: self.wpm(elapsed) as usize,
+                    wpm,
                     cpm: self.cpm(elapsed) as usize,
                     word_count: self.word_count,
                     mistakes: self.mistakes,
                     accuracy,
+                    suspected_paste: self.suspected_paste(),
+                    word_speeds: word_speeds(
+                        &self.text_chars,
+                        &self.index_times,
+                        now,
+                        self.options.wpm_divisor,
+                    ),
+                    time_to_first: self.time_to_first.unwrap_or_default(),
+                    kspm: (self.keystrokes as f32 / (elapsed.as_secs_f32() / 60.0)) as usize,
+                    peak_wpm: peak_wpm(&self.key_times, PEAK_KEYS, self.options.wpm_divisor)
+                        as usize,
+                    errors: self.errors,
+                    slowest_key: slowest_key(&self.key_chars, &self.key_times),
+                    remaining: self.text_chars.len().saturating_sub(self.checked.len()),
+                    reason,
+                    edit_accuracy: self.edit_accuracy(accuracy),
+                    skipped_words: self.skipped_words,
+                    perfect: reason == FinishReason::Completed
+                        && self.mistakes == 0
+                        && wpm >= self.options.perfect_wpm,
                 };
             }
         }
     }
 }
 
+// The highest wpm over `window` consecutive keystrokes,
+// or over all of them if there are fewer.
+fn peak_wpm(times: &[Instant], window: usize, wpm_divisor: f32) -> f32 {
+    times
+        .windows(window.min(times.len()).max(2))
+        .map(|w| {
+            let minutes = w[w.len() - 1]
+                .saturating_duration_since(w[0])
+                .as_secs_f32()
+                .max(0.001)
+                / 60.0;
+            // The first keystroke only starts the clock.
+            (w.len() - 1) as f32 / wpm_divisor / minutes
+        })
+        .fold(0.0, f32::max)
+}
+
+fn windowed_wpm(times: &[Instant], window: Duration, now: Instant, wpm_divisor: f32) -> f32 {
+    let first = match times.first() {
+        Some(&first) => first,
+        None => return 0.0,
+    };
+
+    // Like `peak_wpm`, the first keystroke only starts the clock.
+    let from = now.checked_sub(window).unwrap_or(first).max(first);
+    let keys = times.iter().filter(|&&t| t > from && t <= now).count();
+    let minutes = now.saturating_duration_since(from).as_secs_f32().max(0.001) / 60.0;
+    keys as f32 / wpm_divisor / minutes
+}
+
+// The Levenshtein distance: the fewest chars to insert, remove or replace
+// to turn `a` into `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replace = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = replace.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+// The longest pause between two keystrokes, and the key typed after it.
+// The first keystroke has no pause before it, see `time_to_first` for that.
+fn slowest_key(chars: &[char], times: &[Instant]) -> Option<(char, Duration)> {
+    times
+        .windows(2)
+        .map(|w| w[1].saturating_duration_since(w[0]))
+        .zip(chars.iter().skip(1))
+        .map(|(pause, &c)| (c, pause))
+        .max_by_key(|(_, pause)| *pause)
+}
+
+// The speed of every word in the text.
+// A word is timed from the last keystroke before it (normally the space,
+// or the start of the game) to the keystroke on its last character.
+// Not from its own first character: that would time n characters over
+// n - 1 keystroke intervals, and leave one character words without a time.
+// So a pause before a word counts against the word, like in the wpm.
+// Words that have characters without a keystroke were skipped.
+fn word_speeds(
+    text: &[char],
+    times: &[Option<Instant>],
+    start: Instant,
+    wpm_divisor: f32,
+) -> Vec<WordSpeed> {
+    let mut speeds = Vec::new();
+    let mut index = 0;
+
+    while index < text.len() {
+        if text[index] == ' ' {
+            index += 1;
+            continue;
+        }
+
+        let end = text[index..]
+            .iter()
+            .position(|&c| c == ' ')
+            .map(|pos| index + pos)
+            .unwrap_or(text.len());
+
+        let word = text[index..end].iter().collect::<String>();
+        let typed = times[index..end].iter().all(Option::is_some);
+        let wpm = match times[end - 1] {
+            Some(last) if typed => {
+                let first = times[..index]
+                    .iter()
+                    .rev()
+                    .flatten()
+                    .next()
+                    .copied()
+                    .unwrap_or(start);
+                let minutes = last
+                    .saturating_duration_since(first)
+                    .as_secs_f32()
+                    .max(0.001)
+                    / 60.0;
+                Some((end - index) as f32 / wpm_divisor / minutes)
+            }
+            _ => None,
+        };
+
+        speeds.push(WordSpeed { word, wpm });
+        index = end;
+    }
+
+    speeds
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_wpm() {
         let words = vec!["fives".to_string(), "fives".into(), "fives".into()];
-        let gs = Game::new(&words, false, false);
+        let mut gs = Game::new(&words, GameOptions::default()).unwrap();
+        "fives fives fives".chars().for_each(|c| gs.push(c));
         let wpm = gs.wpm(Duration::from_secs(60));
         assert_eq!(wpm as usize, 3);
+
+        let options = GameOptions {
+            wpm_divisor: 3.0,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&words, options).unwrap();
+        "fives fives fives".chars().for_each(|c| gs.push(c));
+        let wpm = gs.wpm(Duration::from_secs(60));
+        assert_eq!(wpm as usize, 5);
+
+        // Only the typed chars count.
+        let mut gs = Game::new(&words, GameOptions::default()).unwrap();
+        "fives ".chars().for_each(|c| gs.push(c));
+        let wpm = gs.wpm(Duration::from_secs(60));
+        assert_eq!(wpm as usize, 1);
     }
 
     #[test]
     fn test_word_count() {
         let words = vec!["one".to_string(), "two".into(), "three".into()];
-        let gs = Game::new(&words, false, false);
+        let gs = Game::new(&words, GameOptions::default()).unwrap();
         assert_eq!(gs.word_count, 3);
     }
 
     #[test]
     fn test_mistakes() {
-        let mut gs = Game::new(&vec!["one".into()], false, false);
+        let mut gs = Game::new(&["one".into()], GameOptions::default()).unwrap();
         gs.push('o');
         assert_eq!(gs.mistakes, 0);
         gs.push('o');
@@ -216,4 +877,775 @@ mod test {
         gs.push('n');
         assert_eq!(gs.mistakes, 1);
     }
+
+    #[test]
+    fn test_suspected_paste() {
+        let words = vec!["pasted".to_string()];
+        let options = GameOptions {
+            paste_threshold: 5,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&words, options).unwrap();
+        "pasted".chars().for_each(|c| gs.push(c));
+        assert!(matches!(
+            gs.state,
+            GameState::Finished {
+                suspected_paste: true,
+                ..
+            }
+        ));
+
+        let mut gs = Game::new(&words, GameOptions::default()).unwrap();
+        "pasted".chars().for_each(|c| gs.push(c));
+        assert!(matches!(
+            gs.state,
+            GameState::Finished {
+                suspected_paste: false,
+                ..
+            }
+        ));
+    }
+
+    // Remove trailing spaces, then everything after the last remaining space.
+    fn without_last_word(input: &str) -> &str {
+        let trimmed = input.trim_end_matches(' ');
+        match trimmed.rfind(' ') {
+            Some(pos) => &trimmed[..=pos],
+            None => "",
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn pop_word_removes_last_word(input in "[a-zé ]{0,24}") {
+            let mut gs = Game::new(&["unused".into()], GameOptions::default()).unwrap();
+            input.chars().for_each(|c| gs.push_input(c));
+            gs.pop_word();
+            prop_assert_eq!(gs.input.as_str(), without_last_word(&input));
+        }
+    }
+
+    #[test]
+    fn pop_word_on_empty_input() {
+        let mut gs = Game::new(&["one".into()], GameOptions::default()).unwrap();
+        gs.pop_word();
+        assert_eq!(gs.input, "");
+        assert_eq!(gs.mistakes, 0);
+    }
+
+    #[test]
+    fn pop_word_cases() {
+        assert_eq!(without_last_word("a b  "), "a ");
+        assert_eq!(without_last_word("a bc"), "a ");
+        assert_eq!(without_last_word("abc"), "");
+        assert_eq!(without_last_word(""), "");
+    }
+
+    #[test]
+    fn test_word_speeds() {
+        let text = "ab cde".chars().collect::<Vec<_>>();
+        let start = Instant::now();
+        let at = |ms: u64| Some(start + Duration::from_millis(ms));
+
+        // "ab" takes 1.2 seconds, "cde" takes 3.6 seconds from the space.
+        let times = vec![at(600), at(1200), at(1400), at(2000), at(3000), at(5000)];
+        let speeds = word_speeds(&text, &times, start, DEFAULT_WPM_DIVISOR);
+        assert_eq!(speeds.len(), 2);
+        assert_eq!(speeds[0].word, "ab");
+        assert_eq!(speeds[0].wpm.unwrap().round(), 20.0);
+        assert_eq!(speeds[1].word, "cde");
+        assert_eq!(speeds[1].wpm.unwrap().round(), 10.0);
+
+        // "ab" was skipped by pressing space after "a"
+        let times = vec![at(600), None, at(1400), at(2000), at(3000), at(5000)];
+        let speeds = word_speeds(&text, &times, start, DEFAULT_WPM_DIVISOR);
+        assert_eq!(speeds[0].wpm, None);
+        assert_eq!(speeds[1].wpm.unwrap().round(), 10.0);
+    }
+
+    #[test]
+    fn skipped_words_have_no_speed() {
+        let words = vec!["one".to_string(), "two".into()];
+        let options = GameOptions {
+            skip_word_on_space: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&words, options).unwrap();
+        "o two ".chars().for_each(|c| gs.push(c));
+
+        match gs.state {
+            GameState::Finished { word_speeds, .. } => {
+                assert_eq!(word_speeds[0].wpm, None);
+                assert!(word_speeds[1].wpm.is_some());
+            }
+            _ => panic!("game should be finished"),
+        }
+    }
+
+    #[test]
+    fn test_wpm_samples() {
+        let mut gs = Game::new(&["hello".into(), "world".into()], GameOptions::default()).unwrap();
+        let start = Instant::now();
+
+        // Nothing is sampled before the first keystroke.
+        gs.tick(start + Duration::from_secs(5));
+        assert!(gs.wpm_samples().is_empty());
+
+        "hello".chars().for_each(|c| gs.push(c));
+        let start = match gs.state {
+            GameState::Running(start) => start,
+            _ => panic!("game should be running"),
+        };
+
+        gs.tick(start + Duration::from_millis(500));
+        assert!(gs.wpm_samples().is_empty());
+
+        // Five correct characters after one second is 60 wpm,
+        // and 30 wpm after two seconds.
+        gs.tick(start + Duration::from_millis(2100));
+        let samples = gs
+            .wpm_samples()
+            .iter()
+            .map(|s| s.round())
+            .collect::<Vec<_>>();
+        assert_eq!(samples, vec![60.0, 30.0]);
+    }
+
+    #[test]
+    fn state_helpers() {
+        let mut gs = Game::new(&["a".into()], GameOptions::default()).unwrap();
+        assert!(gs.is_running() && !gs.is_stopped() && !gs.is_finished());
+
+        gs.state = GameState::Stopped;
+        assert!(gs.is_stopped() && !gs.is_running() && !gs.is_finished());
+
+        gs.start();
+        gs.push('a');
+        assert!(gs.is_finished() && !gs.is_running() && !gs.is_stopped());
+    }
+
+    // The input compared against the text from scratch.
+    fn recompute(gs: &Game) -> Vec<(char, bool)> {
+        gs.input
+            .chars()
+            .zip(gs.text_chars.iter())
+            .map(|(i, t)| (i, i == *t))
+            .collect()
+    }
+
+    #[test]
+    fn cached_input() {
+        let options = GameOptions {
+            skip_word_on_space: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["héllo".into(), "wörld".into(), "again".into()], options).unwrap();
+        "hel".chars().for_each(|c| gs.push(c));
+        gs.pop();
+        "éllo w ag".chars().for_each(|c| gs.push(c));
+        assert_eq!(gs.input(), recompute(&gs));
+
+        gs.pop_word();
+        assert_eq!(gs.input(), recompute(&gs));
+        gs.pop();
+        gs.pop();
+        gs.push('x');
+        assert_eq!(gs.input(), recompute(&gs));
+        assert_eq!(gs.input.chars().count(), gs.input().len());
+    }
+
+    #[test]
+    fn test_pacer_index() {
+        let mut gs = Game::new(&["fives".into(), "fives".into()], GameOptions::default()).unwrap();
+        assert_eq!(gs.pacer_index(60.0, Instant::now()), None);
+
+        gs.push('f');
+        let GameState::Running(start) = gs.state else {
+            panic!("not running")
+        };
+        // 60 wpm is five chars a second.
+        assert_eq!(gs.pacer_index(60.0, start), Some(0));
+        assert_eq!(
+            gs.pacer_index(60.0, start + Duration::from_secs(1)),
+            Some(5)
+        );
+        assert_eq!(
+            gs.pacer_index(60.0, start + Duration::from_secs(60)),
+            Some(11)
+        );
+    }
+
+    #[test]
+    fn test_ghost_index() {
+        let words = ["ab".to_string(), "cd".to_string()];
+        let mut first = Game::new(&words, GameOptions::default()).unwrap();
+        "ab".chars().for_each(|c| first.push(c));
+        let times = first.typing_times();
+        assert_eq!(times.len(), 2);
+        assert_eq!(times[0], Duration::ZERO);
+
+        let mut gs = Game::new(&words, GameOptions::default()).unwrap();
+        gs.set_ghost(vec![
+            Duration::ZERO,
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+        ]);
+        assert_eq!(gs.ghost_index(Instant::now()), None);
+
+        gs.push('a');
+        let GameState::Running(start) = gs.state else {
+            panic!("not running")
+        };
+        assert_eq!(gs.ghost_index(start), Some(1));
+        assert_eq!(gs.ghost_index(start + Duration::from_secs(1)), Some(2));
+        assert_eq!(gs.ghost_index(start + Duration::from_secs(60)), Some(3));
+    }
+
+    #[test]
+    fn test_time_to_first() {
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        gs.start();
+        std::thread::sleep(Duration::from_millis(5));
+        gs.push('a');
+        gs.pop();
+        gs.push('a');
+        gs.push('b');
+
+        let GameState::Finished { time_to_first, .. } = gs.state else {
+            panic!("not finished")
+        };
+        assert!(time_to_first >= Duration::from_millis(5));
+        assert!(time_to_first < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_peak_wpm() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // Five chars a second is 60 wpm, ten chars a second is 120 wpm.
+        let times = vec![at(0), at(200), at(400), at(500), at(600), at(800)];
+        assert_eq!(peak_wpm(&times, 3, 5.0).round(), 120.0);
+        assert_eq!(peak_wpm(&times, 6, 5.0).round(), 75.0);
+        // Fewer keys than the window.
+        assert_eq!(peak_wpm(&times, 10, 5.0).round(), 75.0);
+        assert_eq!(peak_wpm(&times[..1], 3, 5.0), 0.0);
+    }
+
+    #[test]
+    fn test_keystrokes() {
+        let mut gs = Game::new(&["ab cd".into()], GameOptions::default()).unwrap();
+        "ax".chars().for_each(|c| gs.push(c));
+        gs.pop();
+        "b c".chars().for_each(|c| gs.push(c));
+        gs.pop_word();
+        assert_eq!(gs.keystrokes, 7);
+
+        // Clearing the input starts over, like the clock.
+        gs.pop_word();
+        gs.push('a');
+        assert_eq!(gs.keystrokes, 1);
+    }
+
+    #[test]
+    fn finish_early() {
+        let mut gs = Game::new(&["abcd".into(), "efgh".into()], GameOptions::default()).unwrap();
+        "ax".chars().for_each(|c| gs.push(c));
+        gs.finish();
+
+        let GameState::Finished {
+            accuracy, mistakes, ..
+        } = gs.state
+        else {
+            panic!("not finished")
+        };
+        assert_eq!(mistakes, 1);
+        assert_eq!(accuracy, 50.0);
+    }
+
+    #[test]
+    fn strict_blocks_wrong_keys() {
+        let options = GameOptions {
+            strict: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["ab".into()], options).unwrap();
+        gs.push('a');
+        assert!(!gs.blocked());
+
+        gs.push('x');
+        assert!(gs.blocked());
+        assert_eq!(gs.input().len(), 1);
+        assert_eq!(gs.mistakes, 1);
+
+        gs.push('b');
+        assert!(!gs.blocked());
+        assert!(gs.is_finished());
+
+        let mut gs = Game::new(&["ab".into()], options).unwrap();
+        gs.push('x');
+        assert!(gs.blocked());
+        gs.pop();
+        assert!(!gs.blocked());
+    }
+
+    #[test]
+    fn strict_counts_wrong_first_key() {
+        let options = GameOptions {
+            strict: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["ab".into()], options).unwrap();
+        gs.push('x');
+        std::thread::sleep(Duration::from_millis(20));
+        gs.push('a');
+        gs.push('b');
+
+        let GameState::Finished {
+            mistakes, elapsed, ..
+        } = gs.state
+        else {
+            panic!("not finished")
+        };
+        assert_eq!(mistakes, 1);
+        assert_eq!(gs.keystrokes, 3);
+        // Timed from the wrong key.
+        assert!(elapsed >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn skipped_ranges() {
+        let options = GameOptions {
+            skip_word_on_space: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["abc".into(), "de".into(), "f".into()], options).unwrap();
+        "a de ".chars().for_each(|c| gs.push(c));
+        assert_eq!(gs.skipped().to_vec(), vec![1..3]);
+
+        (0..3).for_each(|_| gs.pop());
+        assert_eq!(gs.skipped().to_vec(), vec![1..3]);
+
+        // The padding of the skipped word is deleted at once.
+        gs.pop();
+        assert_eq!(gs.input().len(), 1);
+        assert!(gs.skipped().is_empty());
+    }
+
+    #[test]
+    fn skipped_words() {
+        let options = GameOptions {
+            skip_word_on_space: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["abc".into(), "de".into(), "f".into()], options).unwrap();
+        gs.push('a');
+        gs.push(' ');
+        assert_eq!(gs.skipped_words(), 1);
+        // "bc" and the space after it.
+        assert_eq!(gs.mistakes, 3);
+
+        // Deleting the padding doesn't undo the skip, like the mistakes.
+        gs.pop();
+        assert_eq!(gs.skipped_words(), 1);
+        assert_eq!(gs.mistakes, 3);
+
+        "bc d ".chars().for_each(|c| gs.push(c));
+        assert_eq!(gs.skipped_words(), 2);
+        assert_eq!(gs.mistakes, 5);
+        gs.finish();
+        let GameState::Finished { skipped_words, .. } = gs.state else {
+            panic!("not finished")
+        };
+        assert_eq!(skipped_words, 2);
+    }
+
+    #[test]
+    fn error_kinds() {
+        let errors = |text: &str, input: &str, options: GameOptions| {
+            let mut gs = Game::new(&[text.to_string()], options).unwrap();
+            input.chars().for_each(|c| gs.push(c));
+            gs.finish();
+            let GameState::Finished {
+                errors, mistakes, ..
+            } = gs.state
+            else {
+                panic!("not finished")
+            };
+            assert_eq!(errors.missed + errors.extra + errors.wrong, mistakes);
+            errors
+        };
+        let default = GameOptions::default();
+
+        let e = errors("ab", "a ", default);
+        assert_eq!((e.missed, e.extra, e.wrong), (1, 0, 0));
+
+        let e = errors("a b", "abb", default);
+        assert_eq!((e.missed, e.extra, e.wrong), (0, 1, 0));
+
+        let e = errors("ab", "ax", default);
+        assert_eq!((e.missed, e.extra, e.wrong), (0, 0, 1));
+
+        // Past the end of the text.
+        let e = errors("ab", "xbc", default);
+        assert_eq!((e.missed, e.extra, e.wrong), (0, 1, 1));
+
+        // The rest of a skipped word, and the space after it.
+        let options = GameOptions {
+            skip_word_on_space: true,
+            ..default
+        };
+        let mut gs = Game::new(&["abc".into(), "d".into()], options).unwrap();
+        "a d".chars().for_each(|c| gs.push(c));
+        gs.finish();
+        let GameState::Finished { errors, .. } = gs.state else {
+            panic!("not finished")
+        };
+        assert_eq!((errors.missed, errors.extra, errors.wrong), (3, 0, 0));
+    }
+
+    #[test]
+    fn exact_end() {
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        "ax ".chars().for_each(|c| gs.push(c));
+        assert!(gs.is_finished());
+
+        let options = GameOptions {
+            require_exact_end: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["ab".into()], options).unwrap();
+        "ax ".chars().for_each(|c| gs.push(c));
+        assert!(gs.is_running());
+
+        // The space past the end isn't kept, fixing the last word finishes.
+        assert_eq!(gs.input().len(), 2);
+        gs.pop();
+        gs.push('b');
+        assert!(gs.is_finished());
+    }
+
+    #[test]
+    fn test_slowest_key() {
+        let start = Instant::now();
+        let times = [0, 100, 1300, 1400, 1500].map(|ms| start + Duration::from_millis(ms));
+        let chars = ['a', 'b', 'c', 'd', 'e'];
+        assert_eq!(
+            slowest_key(&chars, &times),
+            Some(('c', Duration::from_millis(1200)))
+        );
+
+        // A single key has no pause.
+        assert_eq!(slowest_key(&chars[..1], &times[..1]), None);
+    }
+
+    #[test]
+    fn one_past_the_end() {
+        let mistakes = |gs: &Game| match gs.state {
+            GameState::Finished { mistakes, .. } => mistakes,
+            _ => gs.mistakes,
+        };
+
+        // A space after a wrong last word finishes, without another mistake.
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        "ax ".chars().for_each(|c| gs.push(c));
+        assert!(gs.is_finished());
+        assert_eq!(mistakes(&gs), 1);
+
+        // Any other char is one extra mistake, even the last char of the text,
+        // and is never part of the input.
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        "axb".chars().for_each(|c| gs.push(c));
+        assert!(gs.is_running());
+        assert_eq!(gs.input().len(), 2);
+        assert_eq!(mistakes(&gs), 2);
+        assert_eq!(gs.errors.extra, 1);
+
+        // Strict mode never gets past a wrong char, a correct text finishes
+        // before there is an end to type past.
+        let options = GameOptions {
+            strict: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["ab".into()], options).unwrap();
+        "ax ".chars().for_each(|c| gs.push(c));
+        assert!(gs.is_running());
+        assert_eq!(gs.input().len(), 1);
+        assert_eq!(mistakes(&gs), 2);
+        gs.push('b');
+        assert!(gs.is_finished());
+    }
+
+    #[test]
+    fn feed_until_finished() {
+        let mut gs = Game::new(&["ab".into(), "cd".into()], GameOptions::default()).unwrap();
+        gs.feed("ab cd and more");
+        assert!(gs.is_finished());
+        assert_eq!(gs.input().len(), 5);
+
+        let options = GameOptions {
+            skip_word_on_space: true,
+            ..GameOptions::default()
+        };
+        let mut gs = Game::new(&["ab".into(), "cd".into()], options).unwrap();
+        gs.feed("a c");
+        assert_eq!(gs.skipped().to_vec(), vec![1..2]);
+    }
+
+    #[test]
+    fn remaining_chars() {
+        let remaining = |input: &str| {
+            let mut gs =
+                Game::new(&["abcd".into(), "efgh".into()], GameOptions::default()).unwrap();
+            gs.feed(input);
+            gs.finish();
+            match gs.state {
+                GameState::Finished { remaining, .. } => remaining,
+                _ => panic!("not finished"),
+            }
+        };
+
+        assert_eq!(remaining("abc"), 6);
+        assert_eq!(remaining("abcd efgh"), 0);
+    }
+
+    #[test]
+    fn empty_text() {
+        assert_eq!(
+            Game::new(&[], GameOptions::default()).err(),
+            Some(Error::ZeroWordCount)
+        );
+        assert_eq!(
+            Game::new(&["".into(), "".into()], GameOptions::default()).err(),
+            Some(Error::ZeroWordCount)
+        );
+    }
+
+    #[test]
+    fn test_windowed_wpm() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let window = Duration::from_secs(10);
+
+        // 5 keys a second for 10 seconds, then 10 keys a second for 10 seconds.
+        let times = (0..=50)
+            .map(|i| at(i * 200))
+            .chain((1..=100).map(|i| at(10_000 + i * 100)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(windowed_wpm(&[], window, at(1000), 5.0), 0.0);
+        // Less than a window of keys, from the first key: 25 keys in 5 seconds.
+        assert_eq!(windowed_wpm(&times[..26], window, at(5000), 5.0), 60.0);
+        assert_eq!(windowed_wpm(&times, window, at(10_000), 5.0), 60.0);
+        assert_eq!(windowed_wpm(&times, window, at(20_000), 5.0), 120.0);
+        // Half of each speed, and slowing down after the last key.
+        assert_eq!(windowed_wpm(&times, window, at(15_000), 5.0), 90.0);
+        assert_eq!(windowed_wpm(&times, window, at(25_000), 5.0), 60.0);
+    }
+
+    #[test]
+    fn typed_and_remaining() {
+        let mut gs = Game::new(&["ab".into(), "c".into()], GameOptions::default()).unwrap();
+        assert_eq!(gs.typed(), &[] as &[char]);
+        assert_eq!(gs.remaining(), &['a', 'b', ' ', 'c']);
+
+        gs.push('a');
+        gs.push('x');
+        assert_eq!(gs.typed(), &['a', 'b']);
+        assert_eq!(gs.remaining(), &[' ', 'c']);
+
+        gs.feed(" c");
+        assert_eq!(gs.typed(), &['a', 'b', ' ', 'c']);
+        assert_eq!(gs.remaining(), &[] as &[char]);
+
+        // Clamped, were the input ever longer than the text.
+        gs.checked.push(('d', false));
+        assert_eq!(gs.typed().len(), 4);
+        assert!(gs.remaining().is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("abc"), &chars("abc")), 0);
+        assert_eq!(edit_distance(&chars("abxc"), &chars("abc")), 1);
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+    }
+
+    #[test]
+    fn edit_accuracy_with_an_inserted_char() {
+        let mut gs = Game::new(&["abcdef".into()], GameOptions::default()).unwrap();
+        // The `x` shifts every char after it.
+        gs.feed("abxcdef");
+        gs.finish();
+
+        let GameState::Finished {
+            accuracy,
+            edit_accuracy,
+            ..
+        } = gs.state
+        else {
+            panic!("not finished");
+        };
+        // Four wrong chars and the `f` past the end.
+        assert_eq!(accuracy, 100.0 - 5.0 / 6.0 * 100.0);
+        // Remove the `x` and the `f` is missing.
+        assert_eq!(edit_accuracy, 100.0 - 2.0 / 6.0 * 100.0);
+    }
+
+    #[test]
+    fn pause_when_idle() {
+        let idle = Duration::from_secs(60);
+        let timeout = Duration::from_secs(5);
+        let options = GameOptions {
+            idle_timeout: Some(timeout),
+            paste_threshold: 2,
+            ..Default::default()
+        };
+
+        // A minute between the first and the second key.
+        let play = |options: GameOptions| {
+            let mut gs = Game::new(&["ab".into()], options).unwrap();
+            gs.push('a');
+            if let GameState::Running(start) = &mut gs.state {
+                *start -= idle;
+            }
+            gs.last_key = gs.last_key.map(|last| last - idle);
+            gs.key_times[0] -= idle;
+            gs.index_times[0] = gs.index_times[0].map(|t| t - idle);
+
+            gs.tick(Instant::now());
+            let paused = gs.is_paused();
+            gs.push('b');
+            assert!(!gs.is_paused());
+            match gs.state {
+                GameState::Finished {
+                    elapsed,
+                    slowest_key: Some((_, slowest)),
+                    suspected_paste,
+                    ..
+                } => {
+                    assert!(!suspected_paste);
+                    (paused, elapsed, slowest)
+                }
+                _ => panic!("not finished"),
+            }
+        };
+
+        // Only the timeout before the pause counts.
+        let second = Duration::from_secs(1);
+        let (paused, elapsed, slowest) = play(options);
+        assert!(paused);
+        assert!(elapsed >= timeout && elapsed < timeout + second);
+        assert!(slowest >= timeout && slowest < timeout + second);
+
+        let (paused, elapsed, slowest) = play(GameOptions::default());
+        assert!(!paused);
+        assert!(elapsed >= idle);
+        assert!(slowest >= idle);
+    }
+
+    #[test]
+    fn edit_distance_limit() {
+        let options = GameOptions {
+            edit_distance_limit: 20,
+            ..Default::default()
+        };
+        let words = vec!["word".to_string(); 5];
+        let mut gs = Game::new(&words, options).unwrap();
+        assert!(gs.text_chars.len() > 20);
+
+        // An extra char at the start makes every char after it wrong.
+        gs.feed(&format!("x{}", gs.text.clone()));
+        gs.finish();
+        let GameState::Finished {
+            accuracy,
+            edit_accuracy,
+            ..
+        } = gs.state
+        else {
+            panic!("not finished");
+        };
+        assert_eq!(edit_accuracy, accuracy);
+        assert!(accuracy < 50.0);
+    }
+
+    #[test]
+    fn finish_reasons() {
+        let reason = |gs: &Game| match gs.state {
+            GameState::Finished { reason, .. } => reason,
+            _ => panic!("not finished"),
+        };
+
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        gs.feed("ab");
+        assert_eq!(reason(&gs), FinishReason::Completed);
+
+        // A space past the end finishes too, with or without mistakes.
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        gs.feed("ax ");
+        assert_eq!(reason(&gs), FinishReason::Completed);
+
+        let mut gs = Game::new(&["ab".into()], GameOptions::default()).unwrap();
+        gs.push('a');
+        gs.finish();
+        assert_eq!(reason(&gs), FinishReason::Aborted);
+
+        assert_eq!(
+            "aborted".parse::<FinishReason>().unwrap(),
+            FinishReason::Aborted
+        );
+        assert_eq!(
+            FinishReason::Completed
+                .to_string()
+                .parse::<FinishReason>()
+                .unwrap(),
+            FinishReason::Completed
+        );
+        assert!("quit".parse::<FinishReason>().is_err());
+    }
+
+    #[test]
+    fn pop_word_on_split_words() {
+        // `--word-sep _` types `foo_bar` as two words.
+        let mut gs = Game::new(&["foo".into(), "bar".into()], GameOptions::default()).unwrap();
+        "foo bar".chars().for_each(|c| gs.push(c));
+        gs.pop_word();
+        assert_eq!(gs.input, "foo ");
+    }
+
+    #[test]
+    fn perfect_run() {
+        let perfect = |input: &str, options: GameOptions| {
+            let mut gs = Game::new(&["ab".into(), "cd".into()], options).unwrap();
+            // Slow enough for a wpm below the `perfect_wpm` of 10000.
+            gs.push(input.chars().next().unwrap());
+            std::thread::sleep(Duration::from_millis(50));
+            // Backspace for a deleted mistake.
+            for c in input.chars().skip(1) {
+                match c {
+                    '<' => gs.pop(),
+                    c => gs.push(c),
+                }
+            }
+            gs.finish();
+            let GameState::Finished { perfect, .. } = gs.state else {
+                panic!("not finished")
+            };
+            perfect
+        };
+
+        assert!(perfect("ab cd", GameOptions::default()));
+        // A mistake, even when deleted.
+        assert!(!perfect("ax<b cd", GameOptions::default()));
+        // Stopped early.
+        assert!(!perfect("ab", GameOptions::default()));
+        // Too slow.
+        let options = GameOptions {
+            perfect_wpm: 10_000,
+            ..GameOptions::default()
+        };
+        assert!(!perfect("ab cd", options));
+    }
 }
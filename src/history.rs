@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::gamestate::{FinishReason, Game, GameState, WordSpeed};
+
+// -----------------------------------------------------------------------------
+//     - Run record -
+// -----------------------------------------------------------------------------
+/// A finished run as written to the `--log` file, one tab separated line per run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    /// Seconds since the unix epoch.
+    pub time: u64,
+    pub extension: String,
+    pub wpm: usize,
+    pub cpm: usize,
+    pub accuracy: f32,
+    pub word_count: usize,
+    pub mistakes: usize,
+    pub elapsed: Duration,
+    /// The `--tag` of the run, if any.
+    pub tag: Option<String>,
+    /// `None` in older logs.
+    pub reason: Option<FinishReason>,
+}
+
+impl RunRecord {
+    /// The record of a finished game, if it should be recorded at all.
+    /// Runs below the minimum accuracy, shorter than `config.min_record_secs`
+    /// or that look pasted are shown but not recorded.
+    pub fn new(game: &Game, config: &Config) -> Option<Self> {
+        Self::at_least(game, config, config.min_record_secs)
+    }
+
+    /// The record of a finished game for `--markdown`. Like `new`,
+    /// but short runs are exported too: only `-ma` and pasting keep a run out.
+    pub fn exported(game: &Game, config: &Config) -> Option<Self> {
+        Self::at_least(game, config, 0.0)
+    }
+
+    fn at_least(game: &Game, config: &Config, min_record_secs: f32) -> Option<Self> {
+        let GameState::Finished {
+            elapsed,
+            wpm,
+            cpm,
+            word_count,
+            mistakes,
+            accuracy,
+            reason,
+            suspected_paste,
+            ..
+        } = game.state
+        else {
+            return None;
+        };
+
+        if elapsed.as_secs_f32() < min_record_secs || suspected_paste {
+            return None;
+        }
+
+        if let Some(min) = config.min_accuracy {
+            if accuracy < min {
+                return None;
+            }
+        }
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let inst = Self {
+            time,
+            extension: config.file_extension.clone(),
+            wpm,
+            cpm,
+            accuracy,
+            word_count,
+            mistakes,
+            elapsed,
+            // Tabs and new lines would break up the record.
+            tag: config
+                .tag
+                .as_ref()
+                .map(|tag| tag.replace(['\t', '\n'], " ")),
+            reason: Some(reason),
+        };
+
+        Some(inst)
+    }
+
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{:.1}\t{}\t{}\t{}\t{}\t{}",
+            self.time,
+            self.extension,
+            self.wpm,
+            self.cpm,
+            self.accuracy,
+            self.word_count,
+            self.mistakes,
+            self.elapsed.as_millis(),
+            self.tag.as_deref().unwrap_or(""),
+            self.reason
+                .map(|reason| reason.to_string())
+                .unwrap_or_default()
+        )
+    }
+
+    /// Parse a line written by `to_line`.
+    pub fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let mut next = || fields.next();
+
+        let inst = Self {
+            time: next()?.parse().ok()?,
+            extension: next()?.to_string(),
+            wpm: next()?.parse().ok()?,
+            cpm: next()?.parse().ok()?,
+            accuracy: next()?.parse().ok()?,
+            word_count: next()?.parse().ok()?,
+            mistakes: next()?.parse().ok()?,
+            elapsed: Duration::from_millis(next()?.parse().ok()?),
+            // Older logs have no tags.
+            tag: next().filter(|tag| !tag.is_empty()).map(str::to_string),
+            reason: next().and_then(|reason| reason.parse().ok()),
+        };
+
+        Some(inst)
+    }
+
+    /// The record as a markdown table, followed by a table
+    /// with the speed of every word.
+    pub fn to_markdown(&self, word_speeds: &[WordSpeed], config: &Config) -> String {
+        let mut md = format!(
+            "| extension | wpm | cpm | accuracy | words | mistakes | seconds |
+|-----------|-----|-----|----------|-------|----------|---------|
+| {} | {} | {} | {} | {} | {} | {:.1} |
+",
+            self.extension,
+            self.wpm,
+            self.cpm,
+            config.accuracy(self.accuracy, 1),
+            self.word_count,
+            self.mistakes,
+            self.elapsed.as_secs_f32()
+        );
+
+        if !word_speeds.is_empty() {
+            md.push_str("\n| word | wpm |\n|------|-----|\n");
+            for speed in word_speeds {
+                let wpm = match speed.wpm {
+                    Some(wpm) => format!("{:.0}", wpm),
+                    None => "skipped".into(),
+                };
+                // Code is full of pipes.
+                let word = speed.word.replace('|', "\\|");
+                md.push_str(&format!("| `{}` | {} |\n", word, wpm));
+            }
+        }
+
+        md
+    }
+}
+
+/// Write the record as markdown, replacing the file if it exists.
+pub fn write_markdown(
+    path: &Path,
+    record: &RunRecord,
+    word_speeds: &[WordSpeed],
+    config: &Config,
+) -> Result<()> {
+    std::fs::write(path, record.to_markdown(word_speeds, config))
+        .map_err(|e| Error::Write(format!("{}: {}", path.display(), e)))
+}
+
+/// Read every record in the log file. Lines that can't be parsed
+/// are skipped, and a missing file has no records.
+pub fn read(path: &Path) -> Result<Vec<RunRecord>> {
+    match std::fs::read_to_string(path) {
+        Ok(log) => Ok(log.lines().filter_map(RunRecord::from_line).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(Error::Read(format!("{}: {}", path.display(), e))),
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Stats -
+// -----------------------------------------------------------------------------
+/// The runs of a single file extension, as shown by `--stats`.
+/// Languages are typed at very different speeds, so they are never mixed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub extension: String,
+    pub runs: usize,
+    pub average_wpm: f32,
+    pub best_wpm: usize,
+    pub average_accuracy: f32,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} runs | wpm: {:.0} (best: {}) | accuracy: {:.1}%",
+            self.extension, self.runs, self.average_wpm, self.best_wpm, self.average_accuracy
+        )
+    }
+}
+
+/// The stats of every extension in the records, ordered by extension.
+pub fn stats(records: &[RunRecord]) -> Vec<Stats> {
+    let mut by_extension = BTreeMap::<&str, Vec<&RunRecord>>::new();
+    for record in records {
+        by_extension
+            .entry(&record.extension)
+            .or_default()
+            .push(record);
+    }
+
+    by_extension
+        .into_iter()
+        .map(|(extension, records)| {
+            let runs = records.len();
+            Stats {
+                extension: extension.to_string(),
+                runs,
+                average_wpm: records.iter().map(|r| r.wpm as f32).sum::<f32>() / runs as f32,
+                best_wpm: records.iter().map(|r| r.wpm).max().unwrap_or(0),
+                average_accuracy: records.iter().map(|r| r.accuracy).sum::<f32>() / runs as f32,
+            }
+        })
+        .collect()
+}
+
+/// Append the record to the log file, creating the file if needed.
+pub fn append(path: &Path, record: &RunRecord) -> Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", record.to_line()))
+        .map_err(|e| Error::Write(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn finished(elapsed: Duration, accuracy: f32) -> Game {
+        let mut game = Game::new(&["one".into()], Default::default()).unwrap();
+        game.state = GameState::Finished {
+            elapsed,
+            wpm: 40,
+            cpm: 200,
+            word_count: 1,
+            mistakes: 0,
+            accuracy,
+            suspected_paste: false,
+            word_speeds: Vec::new(),
+            time_to_first: Duration::ZERO,
+            peak_wpm: 50,
+            kspm: 220,
+            errors: Default::default(),
+            slowest_key: None,
+            remaining: 0,
+            edit_accuracy: accuracy,
+            skipped_words: 0,
+            perfect: false,
+            reason: FinishReason::Completed,
+        };
+        game
+    }
+
+    fn config(args: &str) -> Config {
+        let args = format!("tccst {} /", args);
+        Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap()
+    }
+
+    #[test]
+    fn record_gate() {
+        let config = config("-ma 90");
+        assert!(RunRecord::new(&finished(Duration::from_secs(5), 100.0), &config).is_some());
+
+        // Too short
+        assert!(RunRecord::new(&finished(Duration::from_millis(500), 100.0), &config).is_none());
+
+        // Too inaccurate
+        assert!(RunRecord::new(&finished(Duration::from_secs(5), 80.0), &config).is_none());
+
+        // Not finished
+        let game = Game::new(&["one".into()], Default::default()).unwrap();
+        assert!(RunRecord::new(&game, &config).is_none());
+
+        // Pasted
+        let mut game = finished(Duration::from_secs(5), 100.0);
+        if let GameState::Finished {
+            suspected_paste, ..
+        } = &mut game.state
+        {
+            *suspected_paste = true;
+        }
+        assert!(RunRecord::new(&game, &config).is_none());
+
+        // Exported anyway, but not when too inaccurate.
+        let short = finished(Duration::from_millis(500), 100.0);
+        assert!(RunRecord::exported(&short, &config).is_some());
+        assert!(RunRecord::exported(&finished(Duration::from_secs(5), 80.0), &config).is_none());
+
+        let config = self::config("--min-record-secs 0");
+        assert!(RunRecord::new(&finished(Duration::from_millis(500), 100.0), &config).is_some());
+    }
+
+    #[test]
+    fn append_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.tsv");
+        let record = RunRecord::new(&finished(Duration::from_secs(5), 100.0), &config("")).unwrap();
+
+        append(&path, &record).unwrap();
+        append(&path, &record).unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(log.lines().count(), 2);
+        assert!(log
+            .lines()
+            .all(|line| line.ends_with("\trs\t40\t200\t100.0\t1\t0\t5000\t\tcompleted")));
+
+        // Older logs don't have the reason.
+        let old = RunRecord::from_line("1\trs\t40\t200\t100.0\t1\t0\t5000\t").unwrap();
+        assert_eq!(old.reason, None);
+    }
+
+    #[test]
+    fn markdown_table() {
+        let record = RunRecord::new(&finished(Duration::from_secs(5), 100.0), &config("")).unwrap();
+        let speeds = [
+            WordSpeed {
+                word: "a||b".into(),
+                wpm: Some(41.6),
+            },
+            WordSpeed {
+                word: "c".into(),
+                wpm: None,
+            },
+        ];
+
+        let md = record.to_markdown(&speeds, &config(""));
+        let lines = md.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[..3],
+            [
+                "| extension | wpm | cpm | accuracy | words | mistakes | seconds |",
+                "|-----------|-----|-----|----------|-------|----------|---------|",
+                "| rs | 40 | 200 | 100.0% | 1 | 0 | 5.0 |",
+            ]
+        );
+        assert_eq!(lines[6..], ["| `a\\|\\|b` | 42 |", "| `c` | skipped |"]);
+
+        assert_eq!(record.to_markdown(&[], &config("")).lines().count(), 3);
+        assert!(record
+            .to_markdown(&[], &config("--no-units --decimals 0"))
+            .contains("| 100 |"));
+    }
+
+    #[test]
+    fn read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.tsv");
+        assert_eq!(read(&path).unwrap(), Vec::new());
+
+        let record = RunRecord::new(&finished(Duration::from_secs(5), 100.0), &config("")).unwrap();
+        append(&path, &record).unwrap();
+        std::fs::write(&path, std::fs::read_to_string(&path).unwrap() + "garbage\n").unwrap();
+        assert_eq!(read(&path).unwrap(), vec![record]);
+    }
+
+    #[test]
+    fn stats_per_extension() {
+        let game = finished(Duration::from_secs(5), 90.0);
+        let record = |args: &str, wpm| RunRecord {
+            wpm,
+            ..RunRecord::new(&game, &config(args)).unwrap()
+        };
+        let records = [
+            record("-t rs", 40),
+            record("-t py", 70),
+            record("-t rs", 60),
+        ];
+
+        let stats = stats(&records);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[0].to_string(),
+            "py: 1 runs | wpm: 70 (best: 70) | accuracy: 90.0%"
+        );
+        assert_eq!(
+            stats[1].to_string(),
+            "rs: 2 runs | wpm: 50 (best: 60) | accuracy: 90.0%"
+        );
+    }
+
+    #[test]
+    fn tag_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.tsv");
+        let record = RunRecord::new(
+            &finished(Duration::from_secs(5), 100.0),
+            &config("--tag rust-keywords"),
+        )
+        .unwrap();
+        assert_eq!(record.tag.as_deref(), Some("rust-keywords"));
+
+        append(&path, &record).unwrap();
+        assert_eq!(read(&path).unwrap(), vec![record]);
+
+        // A line from before tags.
+        let line = "1\trs\t40\t200\t100.0\t1\t0\t5000";
+        assert_eq!(RunRecord::from_line(line).unwrap().tag, None);
+    }
+}
@@ -0,0 +1,21 @@
+//! The game behind the `tccst` binary: pick words from the code of a project,
+//! type them and get the results.
+//!
+//! The binary only sets up the terminal and feeds events to an [`app::App`],
+//! so everything here can be reused by other frontends or tests.
+pub mod app;
+pub mod clipboard;
+pub mod config;
+pub mod daily;
+pub mod error;
+pub mod gamestate;
+pub mod history;
+pub mod palette;
+pub mod render;
+pub mod replay;
+pub mod words;
+
+pub use config::Config;
+pub use error::{Error, Result};
+pub use gamestate::{Game, GameOptions, GameState};
+pub use words::{words, Selector};
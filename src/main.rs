@@ -1,266 +1,174 @@
 use std::env::args;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
 
 use tinybit::events::{events, Event, EventModel, KeyCode, KeyEvent, KeyModifiers};
-use tinybit::render::RenderTarget;
-use tinybit::widgets::Text;
-use tinybit::{term_size, Color, Pixel, Renderer, ScreenPos, ScreenSize, StdoutTarget, Viewport};
+use tinybit::{term_size, Renderer, ScreenPos, ScreenSize, StdoutTarget, Viewport};
 
-mod config;
-mod error;
-mod gamestate;
-mod words;
+use tccst::app::{self, App, Flow};
+use tccst::clipboard;
+use tccst::config::Config;
+use tccst::error;
+use tccst::history;
+use tccst::render::{render, render_compare};
+use tccst::replay::{self, Compare};
+use tccst::Selector;
 
-use config::Config;
-use gamestate::{Game, GameState};
-use words::words;
+const FPS: u64 = 20;
+
+// The area the words are picked for with `--dry-run`, whatever the terminal,
+// so the same arguments always print the same words.
+const DRY_RUN_SIZE: (u16, u16) = (80, 24);
 
 // -----------------------------------------------------------------------------
-//     - Render -
+//     - Game loop -
 // -----------------------------------------------------------------------------
-fn render<T: RenderTarget>(
-    game: &Game,
-    config: &Config,
-    viewport: &mut Viewport,
-    renderer: &mut Renderer<T>,
-) {
-    match game.state {
-        GameState::Running(_) => {
-            let input = game.input();
-            let index = input.len();
-            let text = &game.text_chars;
-
-            let char_count = game.text.chars().count() as u16;
-            let lines = char_count / viewport.size.width;
-
-            // Find the starting x value.
-            let mut x = if lines > 0 {
-                1
-            } else {
-                (viewport.size.width - char_count) / 2
-            };
+fn play() -> error::Result<()> {
+    let config = Config::from_args(args())?;
 
-            let mut y = viewport.size.height / 2 - lines / 2;
+    if let (true, Some(log)) = (config.stats, &config.log) {
+        let mut records = history::read(log)?;
+        if let Some(tag) = &config.tag {
+            records.retain(|record| record.tag.as_ref() == Some(tag));
+        }
 
-            for i in 0..char_count as usize {
-                // An input character can either be:
-                // 1. Correct,
-                // 2. Incorrect space over non-space character
-                // 3. Incorrect character over space
-                // 4. Incorrect non-space character over non-space correct character
-                match input.get(i) {
-                    // Correct
-                    Some((c, _)) if *c == text[i] => viewport.draw_pixel(Pixel::new(
-                        text[i],
-                        ScreenPos::new(x, y),
-                        Some(Color::Blue),
-                        None,
-                    )),
-                    // Incorrect space over non-space character
-                    Some((' ', _)) if text[i] != ' ' => viewport.draw_pixel(Pixel::new(
-                        text[i],
-                        ScreenPos::new(x, y),
-                        Some(Color::DarkGrey),
-                        None,
-                    )),
-                    // Incorrect character over space
-                    Some((c, _)) if text[i] == ' ' => viewport.draw_pixel(Pixel::new(
-                        *c,
-                        ScreenPos::new(x, y),
-                        Some(Color::DarkYellow),
-                        None,
-                    )),
-                    Some((_, _)) => viewport.draw_pixel(Pixel::new(
-                        text[i],
-                        ScreenPos::new(x, y),
-                        Some(Color::Red),
-                        None,
-                    )),
-                    None if i == index => viewport.draw_pixel(Pixel::new(
-                        text[i],
-                        ScreenPos::new(x, y),
-                        Some(config.cursor_foreground_color),
-                        Some(config.cursor_background_color),
-                    )),
-                    None => viewport.draw_pixel(Pixel::new(
-                        text[i],
-                        ScreenPos::new(x, y),
-                        Some(Color::White),
-                        None,
-                    )),
-                }
+        history::stats(&records)
+            .iter()
+            .for_each(|stats| println!("{}", stats));
+        return Ok(());
+    }
 
-                x += 1;
-                if x >= viewport.size.width {
-                    x = 1;
-                    y += 1;
-                }
-            }
-        }
-        GameState::Stopped => {
-            let text = "Press any key to start";
-            let x = (viewport.size.width - text.chars().count() as u16) / 2;
-            let y = viewport.size.height / 2;
+    if let Some((first, second)) = &config.compare {
+        return compare(&config, first, second);
+    }
 
-            let text = Text::new(text, None, None);
-            viewport.draw_widget(&text, ScreenPos::new(x, y));
-        }
-        GameState::Finished {
-            elapsed,
-            wpm,
-            cpm,
-            mistakes,
-            word_count,
-            accuracy,
-        } => {
-            // Split the text if the text is too long to fit on one line,
-            // and show the results as multiple lines.
-            let text_chunks: Vec<String> = {
-                let mut result_text = format!(
-                    "time: {} seconds | wpm: {} (cpm: {}) | mistakes: {} | accuracy: {:.2}% | word count: {}",
-                    elapsed.as_secs(),
-                    wpm,
-                    cpm,
-                    mistakes,
-                    accuracy,
-                    word_count
-                );
+    if config.dry_run {
+        let (w, h) = DRY_RUN_SIZE;
+        let mut selector = Selector::new(&config, (w * h) as usize)?;
+        warn_missing(&selector);
+        let words = selector.next()?;
+        // Verbatim "words" are whole lines.
+        let separator = if config.verbatim { "\n" } else { " " };
+        println!("{}", words.join(separator));
+        return Ok(());
+    }
 
-                // If the accuracy is given, and achieved accuracy
-                // is less than the target, don't show the results.
-                match config.min_accuracy {
-                    Some(acc) if accuracy < acc => result_text =format!("Accuracy too low ({:.2}%)", accuracy),
-                    _ => {}
-                }
+    let (w, h) = term_size().expect("could not get terminal size");
+    let mut app = App::new(config, (w * h) as usize)?;
+    warn_missing(&app.selector);
 
-                // If the result text can't fit on screen we split it on
-                // the pipe char.
-                let mut chunks = if result_text.chars().count() as u16 > viewport.size.width {
-                    result_text
-                        .split('|')
-                        .map(str::trim)
-                        .map(String::from)
-                        .collect()
-                } else {
-                    vec![result_text]
-                };
+    let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(w, h));
 
-                // Add one empt line between the result
-                // and the try-again text.
-                chunks.push(String::from(" "));
+    let stdout = StdoutTarget::new().expect("failed to enter raw mode");
+    let mut renderer = Renderer::new(stdout);
 
-                let text = "Try again? Y(es) | N(o) | R(etry same words)".to_string();
+    render(&app, &mut viewport, &mut renderer);
 
-                // Same as for the result text: we split it on the pipe
-                // if it can't fit.
-                if text.chars().count() as u16 > viewport.size.width {
-                    let mut t = text.split('|').map(str::trim).map(String::from).collect();
-                    chunks.append(&mut t);
-                } else {
-                    chunks.push(text);
+    for event in events(EventModel::Fps(FPS)) {
+        match event {
+            Event::Tick => app.tick(Instant::now())?,
+            Event::Resize(w, h) => {
+                viewport.resize(w, h);
+                renderer.clear();
+            }
+            Event::Key(key) => {
+                let was_finished = app.game.is_finished();
+                if app.handle_key(key)? == Flow::Quit {
+                    break;
                 }
 
-                chunks
-            };
+                // Fire and forget, see `clipboard::osc52`.
+                if app.config.copy && !was_finished {
+                    if let Some(summary) = app::summary(&app.game, &app.config) {
+                        print!("{}", clipboard::osc52(&summary));
+                        let _ = io::stdout().flush();
+                    }
+                }
+            }
+        }
 
-            // Get the length of the longest line.
-            let max_width = text_chunks.iter().map(|t| t.chars().count()).max().unwrap() as u16;
+        render(&app, &mut viewport, &mut renderer);
+    }
 
-            let x = (viewport.size.width - max_width) / 2;
-            let mut y = viewport.size.height / 2 - text_chunks.len() as u16 / 2;
+    // Restore the terminal before printing anything.
+    drop(renderer);
 
-            for chunk in text_chunks {
-                let text = Text::new(chunk, None, None);
-                viewport.draw_widget(&text, ScreenPos::new(x, y));
-                y += 1;
-            }
+    if app.config.share {
+        if let Some(summary) = app.summary {
+            println!("{}", summary);
         }
     }
 
-    renderer.render(viewport);
-}
+    // Space after the game in the terminal. Only here, so the output of
+    // --stats and --dry-run stays clean for scripts.
+    eprintln!();
 
-// -----------------------------------------------------------------------------
-//     - Game loop -
-// -----------------------------------------------------------------------------
-fn play() -> error::Result<()> {
-    let config = Config::from_args(args())?;
-    let (w, h) = term_size().expect("could not get terminal size");
-    let mut selected_words = words(&config, (w * h) as usize)?;
+    Ok(())
+}
 
-    let mut game = Game::new(&selected_words, config.strict, config.skip_word_on_space);
+// Play back the two runs of `--compare` until quit, r starts them over.
+// Both files are read before the terminal is taken over, to show any error.
+fn compare(config: &Config, first: &Path, second: &Path) -> error::Result<()> {
+    let mut compare = Compare::new(replay::read(first)?, replay::read(second)?, Instant::now())?;
 
+    let (w, h) = term_size().expect("could not get terminal size");
     let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(w, h));
 
     let stdout = StdoutTarget::new().expect("failed to enter raw mode");
     let mut renderer = Renderer::new(stdout);
 
-    render(&game, &config, &mut viewport, &mut renderer);
+    render_compare(&compare, config, &mut viewport, &mut renderer);
 
-    for event in events(EventModel::Blocking) {
+    for event in events(EventModel::Fps(FPS)) {
         match event {
-            Event::Tick => unreachable!(),
+            Event::Tick => (),
             Event::Resize(w, h) => {
                 viewport.resize(w, h);
                 renderer.clear();
             }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('w'),
-                modifiers: KeyModifiers::CONTROL,
-            }) => game.pop_word(),
             Event::Key(KeyEvent {
                 code: KeyCode::Char('c'),
                 modifiers: KeyModifiers::CONTROL,
             }) => break,
             Event::Key(KeyEvent {
-                code: KeyCode::Char(c),
-                ..
-            }) => match game.state {
-                GameState::Finished { .. } => match c {
-                    'y' => {
-                        selected_words = words(&config, (w * h) as usize)?;
-                        game = Game::new(&selected_words, config.strict, config.skip_word_on_space);
-                        game.start();
-                    }
-                    'r' => game = Game::new(&selected_words, config.strict, config.skip_word_on_space),
-                    'n' => break,
-                    _ => {}
-                },
-                GameState::Running(_) => {
-                    game.push(c);
-                }
-                GameState::Stopped => game.start(),
-            },
-            Event::Key(KeyEvent {
-                code: KeyCode::Enter,
+                code: KeyCode::Char('r'),
                 ..
-            }) if game.state == GameState::Stopped => game.start(),
+            }) => compare.restart(Instant::now()),
             Event::Key(KeyEvent {
-                code: KeyCode::Backspace,
+                code: KeyCode::Char('q') | KeyCode::Char('n') | KeyCode::Esc,
                 ..
-            }) => game.pop(),
+            }) => break,
             Event::Key(_) => (),
         }
 
-        render(&game, &config, &mut viewport, &mut renderer);
+        render_compare(&compare, config, &mut viewport, &mut renderer);
     }
 
+    drop(renderer);
+    eprintln!();
+
     Ok(())
 }
 
+// Printed before the game takes over the terminal.
+fn warn_missing(selector: &Selector) {
+    for path in selector.missing() {
+        eprintln!(
+            "Warning: {} is listed but doesn't exist, skipped",
+            path.display()
+        );
+    }
+}
+
 fn main() {
     match play() {
         Ok(()) => (),
-        Err(e) if e == error::Error::NeedsHelp => println!("{}", e.to_string()),
-        Err(e) if e == error::Error::Version => println!("{}", e.to_string()),
+        Err(e) if e == error::Error::NeedsHelp => println!("{}", e),
+        Err(e) if e == error::Error::Version => println!("{}", e),
         Err(e) => {
-            eprintln!(
-                "{}\nError: {}",
-                error::Error::NeedsHelp.to_string(),
-                e.to_string()
-            );
+            eprintln!("{}\nError: {}", error::Error::NeedsHelp, e);
             std::process::exit(1);
         }
     }
-
-    eprintln!("");
 }
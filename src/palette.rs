@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use tinybit::Color;
+
+use crate::error::Error;
+
+/// Foreground and background colour of a single cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+impl Style {
+    pub const fn new(fg: Option<Color>, bg: Option<Color>) -> Self {
+        Self { fg, bg }
+    }
+
+    /// The same style with a dimmer foreground, see `dim`.
+    pub fn dimmed(self) -> Self {
+        Self {
+            fg: self.fg.map(dim),
+            ..self
+        }
+    }
+}
+
+/// The darker variant of a colour. The dark colours and the greys turn
+/// a darker grey, so whatever is dimmed stays less visible than the text.
+pub fn dim(color: Color) -> Color {
+    match color {
+        Color::White => Color::Grey,
+        Color::Grey => Color::DarkGrey,
+        Color::Red => Color::DarkRed,
+        Color::Green => Color::DarkGreen,
+        Color::Yellow => Color::DarkYellow,
+        Color::Blue => Color::DarkBlue,
+        Color::Magenta => Color::DarkMagenta,
+        Color::Cyan => Color::DarkCyan,
+        Color::Rgb { r, g, b } => Color::Rgb {
+            r: r / 2,
+            g: g / 2,
+            b: b / 2,
+        },
+        Color::Black => Color::Black,
+        // Grey 238 of the 256 colours, darker than `DarkGrey`.
+        _ => Color::AnsiValue(238),
+    }
+}
+
+/// Colours used to draw the text while the game is running.
+///
+/// The accessible palettes don't rely on hue alone: every error category
+/// is drawn inverted (with a background colour) so it can be told apart
+/// from correct and untyped text by brightness as well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// Correctly typed character.
+    pub correct: Style,
+    /// A space typed over a non-space character.
+    pub missed: Style,
+    /// A character typed over a space.
+    pub extra: Style,
+    /// A wrong character typed over a non-space character.
+    pub wrong: Style,
+    /// Text that hasn't been typed yet.
+    pub untyped: Style,
+    /// The character under the cursor.
+    pub cursor: Style,
+    /// The character under the pacer, see `--pace`.
+    pub pacer: Style,
+    /// The cursor of the last run, see `--ghost`, and of the second run of `--compare`.
+    pub ghost: Style,
+    /// The cursor after a wrong key was rejected in strict mode.
+    pub blocked: Style,
+    /// A character of a word skipped with `-ss`.
+    pub skipped: Style,
+}
+
+impl Palette {
+    /// `correct`: blue, `missed`: dark grey, `extra`: dark yellow,
+    /// `wrong`: red, `untyped`: white, `cursor`: black on blue,
+    /// `pacer`: black on dark magenta, `ghost`: black on dark cyan,
+    /// `blocked`: black on red, `skipped`: black on dark grey.
+    pub const DEFAULT: Palette = Palette {
+        correct: Style::new(Some(Color::Blue), None),
+        missed: Style::new(Some(Color::DarkGrey), None),
+        extra: Style::new(Some(Color::DarkYellow), None),
+        wrong: Style::new(Some(Color::Red), None),
+        untyped: Style::new(Some(Color::White), None),
+        cursor: Style::new(Some(Color::Black), Some(Color::Blue)),
+        pacer: Style::new(Some(Color::Black), Some(Color::DarkMagenta)),
+        ghost: Style::new(Some(Color::Black), Some(Color::DarkCyan)),
+        blocked: Style::new(Some(Color::Black), Some(Color::Red)),
+        skipped: Style::new(Some(Color::Black), Some(Color::DarkGrey)),
+    };
+
+    /// Avoids red / green and uses blue / yellow instead.
+    ///
+    /// `correct`: blue, `missed`: black on grey, `extra`: black on magenta,
+    /// `wrong`: black on yellow, `untyped`: white, `cursor`: black on cyan,
+    /// `pacer`: white on dark blue, `ghost`: black on white,
+    /// `blocked`: black on yellow, `skipped`: white on dark grey.
+    pub const COLORBLIND: Palette = Palette {
+        correct: Style::new(Some(Color::Blue), None),
+        missed: Style::new(Some(Color::Black), Some(Color::Grey)),
+        extra: Style::new(Some(Color::Black), Some(Color::Magenta)),
+        wrong: Style::new(Some(Color::Black), Some(Color::Yellow)),
+        untyped: Style::new(Some(Color::White), None),
+        cursor: Style::new(Some(Color::Black), Some(Color::Cyan)),
+        pacer: Style::new(Some(Color::White), Some(Color::DarkBlue)),
+        ghost: Style::new(Some(Color::Black), Some(Color::White)),
+        blocked: Style::new(Some(Color::Black), Some(Color::Yellow)),
+        skipped: Style::new(Some(Color::White), Some(Color::DarkGrey)),
+    };
+
+    /// Bright text on dark, and dark text on bright for errors.
+    ///
+    /// `correct`: white, `missed`: black on white, `extra`: black on yellow,
+    /// `wrong`: white on red, `untyped`: dark grey, `cursor`: black on cyan,
+    /// `pacer`: black on magenta, `ghost`: white on dark blue,
+    /// `blocked`: white on red, `skipped`: black on grey.
+    pub const HIGH_CONTRAST: Palette = Palette {
+        correct: Style::new(Some(Color::White), None),
+        missed: Style::new(Some(Color::Black), Some(Color::White)),
+        extra: Style::new(Some(Color::Black), Some(Color::Yellow)),
+        wrong: Style::new(Some(Color::White), Some(Color::Red)),
+        untyped: Style::new(Some(Color::DarkGrey), None),
+        cursor: Style::new(Some(Color::Black), Some(Color::Cyan)),
+        pacer: Style::new(Some(Color::Black), Some(Color::Magenta)),
+        ghost: Style::new(Some(Color::White), Some(Color::DarkBlue)),
+        blocked: Style::new(Some(Color::White), Some(Color::Red)),
+        skipped: Style::new(Some(Color::Black), Some(Color::Grey)),
+    };
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl FromStr for Palette {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::DEFAULT),
+            "colorblind" => Ok(Self::COLORBLIND),
+            "high-contrast" => Ok(Self::HIGH_CONTRAST),
+            _ => Err(Error::InvalidPalette),
+        }
+    }
+}
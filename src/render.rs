@@ -0,0 +1,1266 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use tinybit::render::RenderTarget;
+use tinybit::widgets::{Text, Widget};
+use tinybit::{Color, Pixel, Renderer, ScreenPos, ScreenSize, Viewport};
+use unicode_width::UnicodeWidthChar;
+
+use crate::app::{App, Round};
+use crate::config::Config;
+use crate::gamestate::{FinishReason, Game, GameState};
+use crate::palette::Style;
+use crate::replay::{Compare, Lead};
+
+// Number of words listed as the slowest on the results screen.
+const SLOWEST_WORDS: usize = 3;
+
+// Runs shorter than this, or with fewer words, get a note that the wpm
+// is unreliable. A few chars in a fraction of a second extrapolate to silly speeds.
+const RELIABLE_SECS: u64 = 5;
+const RELIABLE_WORDS: usize = 5;
+
+// The live wpm of `--live-wpm` is over the keystrokes of this long ago.
+const LIVE_WPM_WINDOW: Duration = Duration::from_secs(10);
+
+// The grey backgrounds of `--trail`, from next to the cursor
+// to the end of the trail, ANSI greyscale 232 (black) to 255 (white).
+const TRAIL_BRIGHTEST: u8 = 245;
+const TRAIL_DARKEST: u8 = 235;
+
+// The first line of the results of a run without mistakes.
+const PERFECT_RUN: &str = "Perfect run, not a single mistake!";
+
+// Drawn in place of spaces with `--show-whitespace`.
+const WHITESPACE: char = '·';
+
+// -----------------------------------------------------------------------------
+//     - Render -
+// -----------------------------------------------------------------------------
+pub fn render<T: RenderTarget>(app: &App, viewport: &mut Viewport, renderer: &mut Renderer<T>) {
+    let mut pixels = if app.confirm_quit {
+        centered(&[String::from("Quit? Y(es) | N(o)")], viewport.size)
+    } else if app.rounds_done() {
+        rounds(&app.rounds, &app.config, viewport.size)
+    } else {
+        pixels(
+            &app.game,
+            &app.config,
+            app.details,
+            app.previous,
+            viewport.size,
+        )
+    };
+
+    if let (true, Some(label)) = (app.game.is_running(), app.project_label()) {
+        pixels.extend(project_label(label, viewport.size));
+    }
+
+    viewport.draw_pixels(pixels);
+    renderer.render(viewport);
+}
+
+/// The playback of `--compare`, in place of a game.
+pub fn render_compare<T: RenderTarget>(
+    compare: &Compare,
+    config: &Config,
+    viewport: &mut Viewport,
+    renderer: &mut Renderer<T>,
+) {
+    viewport.draw_pixels(compare_pixels(
+        compare,
+        config,
+        Instant::now(),
+        viewport.size,
+    ));
+    renderer.render(viewport);
+}
+
+// -----------------------------------------------------------------------------
+//     - Layout -
+// -----------------------------------------------------------------------------
+// Which project the words are from, in the top right corner.
+fn project_label(label: String, size: ScreenSize) -> Vec<Pixel> {
+    let x = size.width.saturating_sub(label.chars().count() as u16 + 1);
+    text_at(label, ScreenPos::new(x, 0), size)
+}
+
+/// With `details` the results are shown even if the accuracy is too low.
+/// The results of a finished game are compared to the `previous` game, if any.
+pub fn pixels(
+    game: &Game,
+    config: &Config,
+    details: bool,
+    previous: Option<Round>,
+    size: ScreenSize,
+) -> Vec<Pixel> {
+    if size.width == 0 || size.height == 0 {
+        return Vec::new();
+    }
+
+    // Everything is laid out inside the padding, which never takes
+    // more than it leaves.
+    let pad_x = config.padding.min((size.width - 1) / 2);
+    let pad_y = config.padding.min((size.height - 1) / 2);
+    let inner = ScreenSize::new(size.width - pad_x * 2, size.height - pad_y * 2);
+
+    layout(game, config, details, previous, inner)
+        .into_iter()
+        .map(|mut p| {
+            p.pos.x += pad_x;
+            p.pos.y += pad_y;
+            p
+        })
+        .collect()
+}
+
+fn layout(
+    game: &Game,
+    config: &Config,
+    details: bool,
+    previous: Option<Round>,
+    size: ScreenSize,
+) -> Vec<Pixel> {
+    match game.state {
+        // The legend gets the bottom line, and the text is centered above it.
+        GameState::Running(_) if config.legend && size.height > 1 => {
+            let mut pixels = running(game, config, ScreenSize::new(size.width, size.height - 1));
+            pixels.extend(legend(config, size));
+            pixels
+        }
+        GameState::Running(_) => running(game, config, size),
+        GameState::Stopped => {
+            let text = "Press any key to start";
+            let x = size.width.saturating_sub(text.chars().count() as u16) / 2;
+            let y = size.height / 2;
+
+            text_at(text, ScreenPos::new(x, y), size)
+        }
+        GameState::Finished { .. } => finished(game, config, details, previous, size),
+    }
+}
+
+// Text widget pixels, offset by `pos`.
+fn text_at(text: impl Into<String>, pos: ScreenPos, size: ScreenSize) -> Vec<Pixel> {
+    Text::new(text, None, None)
+        .pixels(size)
+        .into_iter()
+        .map(|mut p| {
+            p.pos.x += pos.x;
+            p.pos.y += pos.y;
+            p
+        })
+        .collect()
+}
+
+// If the line can't fit on screen we split it on the pipe char,
+// and wrap each part that still doesn't fit.
+fn fit(line: &str, width: usize) -> Vec<String> {
+    if line.chars().count() <= width {
+        return vec![line.to_string()];
+    }
+
+    line.split('|')
+        .map(str::trim)
+        .flat_map(|part| wrap(part, width))
+        .collect()
+}
+
+// Wrap the text at word boundaries so no line is wider than `width`.
+// A single word that is wider than `width` is truncated with an ellipsis.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let word = if word.chars().count() > width {
+            let mut truncated = word
+                .chars()
+                .take(width.saturating_sub(1))
+                .collect::<String>();
+            truncated.push('…');
+            truncated
+        } else {
+            word.to_string()
+        };
+
+        if line.is_empty() {
+            line = word;
+        } else if line.chars().count() + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(&word);
+        } else {
+            lines.push(line);
+            line = word;
+        }
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Draw the samples as block characters, from lowest to highest value.
+// If there are more samples than fit in `width` they are averaged in chunks.
+fn sparkline(samples: &[f32], width: usize) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let chunk_size = (samples.len() + width - 1) / width.max(1);
+    let values = samples
+        .chunks(chunk_size.max(1))
+        .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+        .collect::<Vec<_>>();
+
+    let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|v| {
+            if range > f32::EPSILON {
+                BLOCKS[((v - min) / range * (BLOCKS.len() - 1) as f32).round() as usize]
+            } else {
+                BLOCKS[BLOCKS.len() / 2]
+            }
+        })
+        .collect()
+}
+
+// Lay out the text: centered if it fits on one line, otherwise wrapped
+// from the left edge and centered vertically. One pixel per char.
+fn place_text(
+    text: &[char],
+    styles: impl Iterator<Item = (char, Style)>,
+    size: ScreenSize,
+) -> Vec<Pixel> {
+    // Wide chars, like most CJK, take two cells.
+    let char_count = text.iter().map(|&c| cell_width(c)).sum::<u16>();
+    let lines = char_count / size.width;
+
+    // Find the starting x value.
+    let mut x = if lines > 0 {
+        1
+    } else {
+        size.width.saturating_sub(char_count) / 2
+    };
+
+    let mut y = (size.height / 2).saturating_sub(lines / 2);
+
+    let mut pixels = Vec::new();
+    for (glyph, style) in styles {
+        // A wide char that doesn't fit in the last column goes on the next line.
+        let width = cell_width(glyph);
+        if x > 1 && x + width > size.width {
+            x = 1;
+            y += 1;
+        }
+
+        pixels.push(Pixel::new(glyph, ScreenPos::new(x, y), style.fg, style.bg));
+
+        x += width;
+        if x >= size.width {
+            x = 1;
+            y += 1;
+        }
+    }
+
+    pixels
+}
+
+// Only spaces in the text are substituted, the comparison
+// with the input is always made against the real space.
+// The dot is a dimmed char of the palette, unless it has a background,
+// like the cursor or a mistake, which has to stay as visible as the rest.
+fn whitespace(glyph: char, style: Style, config: &Config) -> (char, Style) {
+    match glyph {
+        ' ' if config.show_whitespace && style.bg.is_none() => (WHITESPACE, style.dimmed()),
+        ' ' if config.show_whitespace => (WHITESPACE, style),
+        _ => (glyph, style),
+    }
+}
+
+fn running(game: &Game, config: &Config, size: ScreenSize) -> Vec<Pixel> {
+    let input = game.input();
+    let index = input.len();
+    let text = &game.text_chars;
+
+    let palette = &config.palette;
+    let skipped = game.skipped();
+    let now = Instant::now();
+    let pacer = config.pace.and_then(|wpm| game.pacer_index(wpm, now));
+    let ghost = game.ghost_index(now);
+
+    let styles = text.iter().enumerate().map(|(i, &glyph)| {
+        // An input character can either be:
+        // 1. Correct,
+        // 2. Incorrect space over non-space character
+        // 3. Incorrect character over space
+        // 4. Incorrect non-space character over non-space correct character
+        // 5. Part of a word skipped with `-ss`
+        let (glyph, style) = match input.get(i) {
+            Some(_) if skipped.iter().any(|range| range.contains(&i)) => (glyph, palette.skipped),
+            // Correct
+            Some((c, _)) if *c == glyph => (glyph, palette.correct),
+            // Incorrect space over non-space character
+            Some((' ', _)) if glyph != ' ' => (glyph, palette.missed),
+            // Incorrect character over space
+            Some((c, _)) if glyph == ' ' => (*c, palette.extra),
+            Some((_, _)) => (glyph, palette.wrong),
+            None if i == index && game.blocked() => (glyph, palette.blocked),
+            None if i == index => (glyph, palette.cursor),
+            None => (glyph, palette.untyped),
+        };
+
+        // The real cursor is drawn over the pacer, the pacer over the ghost.
+        let style = match (pacer, ghost) {
+            (Some(p), _) if p == i && i != index => palette.pacer,
+            (_, Some(g)) if g == i && i != index => palette.ghost,
+            _ => style,
+        };
+
+        whitespace(glyph, style, config)
+    });
+    let mut pixels = place_text(text, styles, size);
+
+    // The cells before the cursor on the cursor's line, one pixel per char.
+    // The colour of a mistake is kept, the trail only fills empty backgrounds.
+    if let (Some(length), Some(cursor)) = (config.trail, pixels.get(index).map(|p| p.pos.y)) {
+        pixels[index.saturating_sub(length)..index]
+            .iter_mut()
+            .rev()
+            .enumerate()
+            .take_while(|(_, pixel)| pixel.pos.y == cursor)
+            .filter(|(_, pixel)| pixel.bg_color.is_none())
+            .for_each(|(distance, pixel)| pixel.bg_color = Some(trail_shade(distance, length)));
+    }
+
+    // In the top left corner, out of the way of the centered text.
+    if config.percent {
+        let percent = format!("{}%", percent(game));
+        pixels.extend(text_at(percent, ScreenPos::zero(), size));
+    }
+
+    // On the right, below the top right corner of the `project_label`.
+    if config.live_wpm {
+        let wpm = format!("{:.0} wpm", game.windowed_wpm(LIVE_WPM_WINDOW));
+        let x = size.width.saturating_sub(wpm.chars().count() as u16);
+        pixels.extend(text_at(wpm, ScreenPos::new(x, 1), size));
+    }
+
+    pixels
+}
+
+// Fades from the brightest right before the cursor to the darkest
+// at the end of a trail of `length` cells.
+fn trail_shade(distance: usize, length: usize) -> Color {
+    let range = (TRAIL_BRIGHTEST - TRAIL_DARKEST) as usize;
+    let step = distance * range / length.saturating_sub(1).max(1);
+    Color::AnsiValue(TRAIL_BRIGHTEST - step.min(range) as u8)
+}
+
+// How much of the text is typed, at most 100.
+fn percent(game: &Game) -> usize {
+    let typed = game.input().len() as f32 / game.text_chars.len().max(1) as f32;
+    ((typed * 100.0) as usize).min(100)
+}
+
+// Number of terminal cells the char takes, at least one so every
+// char of the text can be told apart from the next.
+fn cell_width(c: char) -> u16 {
+    c.width().unwrap_or(1).max(1) as u16
+}
+
+// What each colour means, in the colours of the palette.
+fn legend(config: &Config, size: ScreenSize) -> Vec<Pixel> {
+    let palette = &config.palette;
+    let entries = [
+        ("correct", palette.correct),
+        ("wrong", palette.wrong),
+        ("extra", palette.extra),
+        ("missed", palette.missed),
+        ("untyped", palette.untyped),
+    ];
+
+    let width = entries
+        .iter()
+        .map(|(label, _)| label.len() + 1)
+        .sum::<usize>()
+        - 1;
+    let mut x = size.width.saturating_sub(width as u16) / 2;
+    let y = size.height - 1;
+
+    let mut pixels = Vec::new();
+    for (label, style) in entries {
+        for glyph in label.chars() {
+            if x < size.width {
+                pixels.push(Pixel::new(glyph, ScreenPos::new(x, y), style.fg, style.bg));
+            }
+            x += 1;
+        }
+        x += 1;
+    }
+
+    pixels
+}
+
+fn finished(
+    game: &Game,
+    config: &Config,
+    details: bool,
+    previous: Option<Round>,
+    size: ScreenSize,
+) -> Vec<Pixel> {
+    let GameState::Finished {
+        elapsed,
+        wpm,
+        cpm,
+        mistakes,
+        word_count,
+        accuracy,
+        suspected_paste,
+        word_speeds,
+        time_to_first,
+        peak_wpm,
+        kspm,
+        errors,
+        slowest_key,
+        remaining,
+        edit_accuracy,
+        reason,
+        skipped_words,
+        perfect,
+    } = &game.state
+    else {
+        return Vec::new();
+    };
+
+    let result_text = if config.no_units {
+        // The same values in the same order, for scripts.
+        format!(
+            "{} | {} | {} | {} | {} | {} | {} | {} | {}",
+            elapsed.as_secs(),
+            wpm,
+            cpm,
+            peak_wpm,
+            mistakes,
+            config.accuracy(*accuracy, 2),
+            word_count,
+            kspm,
+            time_to_first.as_millis()
+        )
+    } else {
+        format!(
+            "time: {} seconds | wpm: {} (cpm: {}, peak: {}) | mistakes: {} | accuracy: {} | word count: {} | keystrokes/min: {} | first key: {} ms",
+            elapsed.as_secs(),
+            wpm,
+            cpm,
+            peak_wpm,
+            mistakes,
+            config.accuracy(*accuracy, 2),
+            word_count,
+            kspm,
+            time_to_first.as_millis()
+        )
+    };
+
+    // If the accuracy is given, and achieved accuracy
+    // is less than the target, don't show the results,
+    // unless the details are asked for.
+    let mut lines = match config.min_accuracy {
+        Some(acc) if *accuracy < acc && details => vec![
+            format!(
+                "Accuracy too low ({}), not recorded",
+                config.accuracy(*accuracy, 2)
+            ),
+            result_text,
+        ],
+        Some(acc) if *accuracy < acc => {
+            vec![format!(
+                "Accuracy too low ({}) | D(etails)",
+                config.accuracy(*accuracy, 2)
+            )]
+        }
+        _ => vec![result_text],
+    };
+
+    // Not for hidden results, that would give the wpm away.
+    let hidden = matches!(config.min_accuracy, Some(acc) if *accuracy < acc) && !details;
+    if let (false, Some(previous)) = (hidden, previous) {
+        let delta = accuracy - previous.accuracy;
+        let sign = if delta < 0.0 { "-" } else { "+" };
+        lines.push(format!(
+            "since the last run: wpm {:+} | accuracy {}{}",
+            *wpm as i64 - previous.wpm as i64,
+            sign,
+            config.accuracy(delta.abs(), 2)
+        ));
+    }
+
+    if *mistakes > 0 {
+        // The chars of the skipped words are in the missed ones.
+        let skipped = if *skipped_words > 0 {
+            format!(" | skipped words: {}", skipped_words)
+        } else {
+            String::new()
+        };
+        lines.push(format!(
+            "wrong: {} | extra: {} | missed: {}{} | accuracy by edit distance: {}",
+            errors.wrong,
+            errors.extra,
+            errors.missed,
+            skipped,
+            config.accuracy(*edit_accuracy, 2)
+        ));
+    }
+
+    let reason = match reason {
+        FinishReason::Completed if *perfect => String::from(PERFECT_RUN),
+        FinishReason::Completed => String::from("Completed!"),
+        FinishReason::Aborted if *remaining > 0 => {
+            format!("Stopped early, {} characters left untyped", remaining)
+        }
+        FinishReason::Aborted => String::from("Stopped early"),
+    };
+    lines.insert(0, reason);
+
+    if let Some((key, pause)) = slowest_key {
+        lines.push(format!(
+            "biggest pause before '{}': {:.1}s",
+            key,
+            pause.as_secs_f32()
+        ));
+    }
+
+    if elapsed.as_secs() < RELIABLE_SECS || *word_count < RELIABLE_WORDS {
+        lines.push(String::from("(too short for a reliable wpm)"));
+    }
+
+    if let Some(pace) = config.pace {
+        if *wpm as f32 >= pace {
+            lines.push(format!("You beat the pacer ({} wpm)", pace));
+        } else {
+            lines.push(format!("The pacer ({} wpm) was faster", pace));
+        }
+    }
+
+    // Too few samples tell nothing more than the wpm itself.
+    let samples = game.wpm_samples();
+    if samples.len() > 1 {
+        let label = "speed: ";
+        let width = (size.width as usize).saturating_sub(label.len()).max(1);
+        lines.push(format!("{}{}", label, sparkline(samples, width)));
+    }
+
+    if let Some(date) = &config.daily {
+        lines.insert(0, format!("Daily challenge {}", date));
+    }
+
+    let mut typed = word_speeds
+        .iter()
+        .filter_map(|s| s.wpm.map(|wpm| (&s.word, wpm)))
+        .collect::<Vec<_>>();
+    typed.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+    if typed.len() > 1 {
+        let slowest = typed
+            .iter()
+            .take(SLOWEST_WORDS)
+            .map(|(word, wpm)| format!("{} ({:.0} wpm)", word, wpm))
+            .collect::<Vec<_>>();
+        lines.push(format!("slowest words: {}", slowest.join(", ")));
+    }
+
+    let skipped = word_speeds
+        .iter()
+        .filter(|s| s.wpm.is_none())
+        .map(|s| s.word.as_str())
+        .collect::<Vec<_>>();
+
+    if !skipped.is_empty() {
+        lines.push(format!("skipped: {}", skipped.join(", ")));
+    }
+
+    if *suspected_paste {
+        lines.push(String::from("This looks like it was pasted, not recorded"));
+    }
+
+    // Add one empt line between the result
+    // and the try-again text.
+    lines.push(String::from(" "));
+    lines.push(String::from("Try again? Y(es) | N(o) | R(etry same words)"));
+
+    let mut pixels = centered(&lines, size);
+
+    // The perfect run line stands out in the colours of the correct chars,
+    // on every row `centered` wraps it and the lines above it to.
+    let perfect_line = lines.iter().position(|line| line == PERFECT_RUN);
+    if let (true, Some(at), Some(top)) =
+        (*perfect, perfect_line, pixels.iter().map(|p| p.pos.y).min())
+    {
+        let rows = |line: &String| fit(line, size.width as usize).len() as u16;
+        let first = top + lines[..at].iter().map(rows).sum::<u16>();
+        let last = first + rows(&lines[at]);
+        let style = config.palette.correct;
+        pixels
+            .iter_mut()
+            .filter(|p| (first..last).contains(&p.pos.y))
+            .for_each(|p| {
+                p.fg_color = style.fg;
+                p.bg_color = style.bg;
+            });
+    }
+
+    pixels
+}
+
+// The results of every round and their average.
+// The accuracy and units follow `--decimals` and `--no-units`, like a game.
+fn rounds(rounds: &[Round], config: &Config, size: ScreenSize) -> Vec<Pixel> {
+    if size.width == 0 || size.height == 0 || rounds.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lines = rounds
+        .iter()
+        .enumerate()
+        .map(|(i, round)| {
+            let accuracy = config.accuracy(round.accuracy, 2);
+            if config.no_units {
+                format!("{} | {} | {}", i + 1, round.wpm, accuracy)
+            } else {
+                format!("round {}: {} wpm | {}", i + 1, round.wpm, accuracy)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let count = rounds.len() as f32;
+    let wpm = rounds.iter().map(|r| r.wpm as f32).sum::<f32>() / count;
+    let accuracy = rounds.iter().map(|r| r.accuracy).sum::<f32>() / count;
+    let (best, best_round) = rounds
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, round)| round.wpm)
+        .unwrap();
+
+    lines.push(String::from(" "));
+    let accuracy = config.accuracy(accuracy, 2);
+    lines.push(if config.no_units {
+        format!(
+            "{:.0} | {} | {} | {}",
+            wpm,
+            accuracy,
+            best + 1,
+            best_round.wpm
+        )
+    } else {
+        format!(
+            "average: {:.0} wpm | accuracy: {} | best: round {} ({} wpm)",
+            wpm,
+            accuracy,
+            best + 1,
+            best_round.wpm
+        )
+    });
+    lines.push(String::from(" "));
+    lines.push(String::from("Play again? Y(es) | N(o)"));
+
+    centered(&lines, size)
+}
+
+// Lines centered on screen, one below the other.
+// The names of the `--compare` files, in the order of the runs.
+fn compare_names(config: &Config) -> [String; 2] {
+    let name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    };
+    match &config.compare {
+        Some((first, second)) => [name(first), name(second)],
+        None => [String::from("first"), String::from("second")],
+    }
+}
+
+/// Both runs of a `--compare` over their shared text, each with its own cursor
+/// and named in that colour on the last line. Once both are played back,
+/// how fast each was and where the winner took the lead for good.
+pub fn compare_pixels(
+    compare: &Compare,
+    config: &Config,
+    now: Instant,
+    size: ScreenSize,
+) -> Vec<Pixel> {
+    if size.width == 0 || size.height < 2 {
+        return Vec::new();
+    }
+
+    let palette = &config.palette;
+    let names = compare_names(config);
+
+    if compare.is_done(now) {
+        let mut lines = compare
+            .elapsed()
+            .iter()
+            .zip(compare.typed().iter())
+            .zip(names.iter())
+            .map(|((elapsed, &typed), name)| {
+                let minutes = elapsed.as_secs_f32() / 60.0;
+                let wpm = match minutes > 0.0 {
+                    true => typed as f32 / config.wpm_divisor / minutes,
+                    false => 0.0,
+                };
+                format!("{}: {:.0} wpm in {:.1}s", name, wpm, elapsed.as_secs_f32())
+            })
+            .collect::<Vec<_>>();
+
+        match compare.lead() {
+            Some(Lead { run, word: 1 }) => {
+                lines.push(format!("{} led from the first word", names[run]))
+            }
+            Some(Lead { run, word }) => lines.push(format!(
+                "{} took the lead for good at word {}",
+                names[run], word
+            )),
+            None => {}
+        }
+        lines.push(String::new());
+        lines.push(String::from("R(eplay) | Q(uit)"));
+        return centered(&lines, size);
+    }
+
+    // The first run's cursor is drawn over the second's.
+    let [first, second] = compare.indices(now);
+    let styles = compare.text.iter().enumerate().map(|(i, &glyph)| {
+        let style = match i {
+            _ if i == first => palette.cursor,
+            _ if i == second => palette.ghost,
+            _ if i < first.max(second) => palette.correct,
+            _ => palette.untyped,
+        };
+        whitespace(glyph, style, config)
+    });
+    let mut pixels = place_text(
+        &compare.text,
+        styles,
+        ScreenSize::new(size.width, size.height - 1),
+    );
+
+    // The names on the last line, in the colour of their cursor.
+    let label = format!("{} {}", names[0], names[1]);
+    let first_len = names[0].chars().count();
+    let x = size.width.saturating_sub(label.chars().count() as u16) / 2;
+    let mut label = text_at(label, ScreenPos::new(x, size.height - 1), size);
+    for (i, pixel) in label.iter_mut().enumerate() {
+        let style = match i.cmp(&first_len) {
+            Ordering::Less => palette.cursor,
+            Ordering::Equal => continue,
+            Ordering::Greater => palette.ghost,
+        };
+        pixel.fg_color = style.fg;
+        pixel.bg_color = style.bg;
+    }
+    pixels.extend(label);
+
+    pixels
+}
+
+fn centered(lines: &[String], size: ScreenSize) -> Vec<Pixel> {
+    // Split the lines that are too long to fit on screen,
+    // and show them as multiple lines.
+    let text_chunks = lines
+        .iter()
+        .flat_map(|line| fit(line, size.width as usize))
+        .collect::<Vec<_>>();
+
+    // Get the length of the longest line.
+    let max_width = text_chunks.iter().map(|t| t.chars().count()).max().unwrap() as u16;
+
+    let x = size.width.saturating_sub(max_width) / 2;
+    let y = (size.height / 2).saturating_sub(text_chunks.len() as u16 / 2);
+
+    text_chunks
+        .into_iter()
+        .enumerate()
+        .flat_map(|(offset, chunk)| text_at(chunk, ScreenPos::new(x, y + offset as u16), size))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gamestate::GameOptions;
+    use crate::palette::Palette;
+
+    fn config() -> Config {
+        Config::from_iter("tccst /".split_whitespace().map(str::to_owned)).unwrap()
+    }
+
+    fn game(words: &[&str]) -> Game {
+        let words = words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        Game::new(&words, GameOptions::default()).unwrap()
+    }
+
+    fn pixel_at(pixels: &[Pixel], x: u16, y: u16) -> Option<Pixel> {
+        pixels
+            .iter()
+            .find(|p| p.pos == ScreenPos::new(x, y))
+            .copied()
+    }
+
+    #[test]
+    fn running_centers_text() {
+        let config = config();
+        let palette = config.palette;
+        let mut game = game(&["ab"]);
+        game.push('x');
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(10, 3));
+        assert_eq!(pixels.len(), 2);
+
+        let a = pixel_at(&pixels, 4, 1).unwrap();
+        assert_eq!(a.glyph, 'a');
+        assert_eq!(a.fg_color, palette.wrong.fg);
+
+        let b = pixel_at(&pixels, 5, 1).unwrap();
+        assert_eq!(b.glyph, 'b');
+        assert_eq!(
+            (b.fg_color, b.bg_color),
+            (palette.cursor.fg, palette.cursor.bg)
+        );
+    }
+
+    #[test]
+    fn running_wraps_long_text() {
+        let config = config();
+        let game = game(&["abcdef"]);
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(4, 3));
+        let positions = pixels
+            .iter()
+            .map(|p| (p.pos.x, p.pos.y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2)]
+        );
+    }
+
+    #[test]
+    fn finished_wider_than_screen() {
+        let config = config();
+        let mut game = game(&["ab"]);
+        game.push('a');
+        game.push('b');
+
+        // The results don't fit in a five column wide screen,
+        // this used to underflow.
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(5, 1));
+        assert!(!pixels.is_empty());
+        assert_eq!(pixels[0].pos, ScreenPos::new(0, 0));
+    }
+
+    #[test]
+    fn wrap_at_word_boundaries() {
+        assert_eq!(wrap("time: 12 seconds", 10), vec!["time: 12", "seconds"]);
+        assert_eq!(wrap("a b c", 5), vec!["a b c"]);
+        assert_eq!(wrap("accuracy: 100.00%", 6), vec!["accur…", "100.0…"]);
+        assert_eq!(wrap("", 6), vec![""]);
+    }
+
+    #[test]
+    fn fit_splits_on_pipes_then_wraps() {
+        assert_eq!(fit("a | b", 10), vec!["a | b"]);
+        assert_eq!(
+            fit("Try again? Y(es) | N(o)", 10),
+            vec!["Try again?", "Y(es)", "N(o)"]
+        );
+    }
+
+    #[test]
+    fn show_whitespace() {
+        let mut config = config();
+        let mut game = game(&["a", "b", "c"]);
+        game.push('a');
+        game.push(' ');
+
+        let text = |pixels: &[Pixel]| pixels.iter().map(|p| p.glyph).collect::<String>();
+        let size = ScreenSize::new(10, 1);
+        assert_eq!(text(&pixels(&game, &config, false, None, size)), "a b c");
+
+        config.show_whitespace = true;
+        let pixels = pixels(&game, &config, false, None, size);
+        assert_eq!(text(&pixels), "a·b·c");
+        assert_eq!(pixels[1].fg_color, config.palette.correct.dimmed().fg);
+        assert_eq!(pixels[3].fg_color, config.palette.untyped.dimmed().fg);
+        assert_ne!(pixels[3].fg_color, pixels[4].fg_color);
+
+        config.palette = Palette::HIGH_CONTRAST;
+        let pixels = self::pixels(&game, &config, false, None, size);
+        assert_eq!(
+            pixels[3].fg_color,
+            Palette::HIGH_CONTRAST.untyped.dimmed().fg
+        );
+        assert_ne!(pixels[3].fg_color, pixels[4].fg_color);
+    }
+
+    #[test]
+    fn sparkline_blocks() {
+        assert_eq!(sparkline(&[0.0, 35.0, 70.0], 10), "▁▅█");
+        assert_eq!(sparkline(&[50.0, 50.0], 10), "▅▅");
+        // Averaged down to fit the width
+        assert_eq!(sparkline(&[0.0, 0.0, 10.0, 10.0], 2), "▁█");
+    }
+
+    #[test]
+    fn rounds_summary() {
+        let rounds = [
+            Round {
+                wpm: 40,
+                accuracy: 100.0,
+            },
+            Round {
+                wpm: 60,
+                accuracy: 90.0,
+            },
+        ];
+
+        let text = |config: &Config| {
+            super::rounds(&rounds, config, ScreenSize::new(80, 10))
+                .iter()
+                .map(|p| p.glyph)
+                .collect::<String>()
+        };
+        let mut config = config();
+        let summary = text(&config);
+        assert!(summary.contains("round 2: 60 wpm | 90.00%"));
+        assert!(summary.contains("average: 50 wpm | accuracy: 95.00% | best: round 2 (60 wpm)"));
+
+        config.decimals = Some(0);
+        config.no_units = true;
+        let summary = text(&config);
+        assert!(summary.contains("2 | 60 | 90"));
+        assert!(summary.contains("50 | 95 | 2 | 60"));
+    }
+
+    #[test]
+    fn pacer_cursor() {
+        let mut config = config();
+        config.pace = Some(1.0);
+        let mut game = game(&["abc"]);
+        game.push('a');
+
+        // The pacer is still on the first char, behind the cursor.
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(10, 1));
+        assert_eq!(pixels[0].bg_color, config.palette.pacer.bg);
+        assert_eq!(pixels[1].bg_color, config.palette.cursor.bg);
+    }
+
+    #[test]
+    fn ghost_and_pacer_cursors() {
+        let mut config = config();
+        config.pace = Some(1.0);
+        let mut game = game(&["abcd"]);
+        game.push('a');
+        game.set_ghost(vec![Duration::ZERO; 2]);
+
+        // The pacer behind the cursor, the ghost ahead of it, each in its own colour.
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(10, 1));
+        assert_eq!(pixels[0].bg_color, config.palette.pacer.bg);
+        assert_eq!(pixels[1].bg_color, config.palette.cursor.bg);
+        assert_eq!(pixels[2].bg_color, config.palette.ghost.bg);
+    }
+
+    #[test]
+    fn compare_runs() {
+        use crate::replay::Replay;
+
+        let config = Config::from_iter(
+            "tccst --compare a.keys b.keys"
+                .split_whitespace()
+                .map(str::to_owned),
+        )
+        .unwrap();
+        let replay = |ms: &[u64]| Replay {
+            words: vec!["ab".into(), "cd".into()],
+            times: ms.iter().copied().map(Duration::from_millis).collect(),
+        };
+        let start = Instant::now();
+        let compare = Compare::new(
+            replay(&[100, 200, 300, 400, 500]),
+            replay(&[200, 300, 350, 380, 450]),
+            start,
+        )
+        .unwrap();
+        let size = ScreenSize::new(60, 8);
+
+        let pixels = compare_pixels(&compare, &config, start + Duration::from_millis(150), size);
+        assert_eq!(pixels[0].bg_color, config.palette.ghost.bg);
+        assert_eq!(pixels[1].bg_color, config.palette.cursor.bg);
+
+        let label = pixels.iter().filter(|p| p.pos.y == 7).collect::<Vec<_>>();
+        assert_eq!(
+            label.iter().map(|p| p.glyph).collect::<String>(),
+            "a.keys b.keys"
+        );
+        assert_eq!(label[0].bg_color, config.palette.cursor.bg);
+        assert_eq!(label[7].bg_color, config.palette.ghost.bg);
+
+        let done = compare_pixels(&compare, &config, start + Duration::from_secs(1), size)
+            .iter()
+            .map(|p| p.glyph)
+            .collect::<String>();
+        assert!(done.contains("a.keys: 120 wpm in 0.5s"));
+        assert!(done.contains("b.keys took the lead for good at word 2"));
+    }
+
+    #[test]
+    fn short_runs_are_unreliable() {
+        let config = config();
+        let mut game = game(&["ab"]);
+        game.push('a');
+        game.push('b');
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(120, 10));
+        let text = pixels.iter().map(|p| p.glyph).collect::<String>();
+        assert!(text.contains("(too short for a reliable wpm)"));
+    }
+
+    #[test]
+    fn legend_on_the_last_line() {
+        let mut config = config();
+        config.legend = true;
+        let game = game(&["ab"]);
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(40, 3));
+        let legend = pixels
+            .iter()
+            .filter(|p| p.pos.y == 2)
+            .map(|p| p.glyph)
+            .collect::<String>();
+        assert_eq!(legend, "correctwrongextramisseduntyped");
+
+        let wrong = pixels.iter().find(|p| p.glyph == 'w').unwrap();
+        assert_eq!(wrong.fg_color, config.palette.wrong.fg);
+
+        // The text is centered in the lines above the legend.
+        assert!(pixels.iter().any(|p| p.glyph == 'a' && p.pos.y == 1));
+    }
+
+    #[test]
+    fn padding() {
+        let config =
+            Config::from_iter("tccst --padding 2 /".split_whitespace().map(str::to_owned)).unwrap();
+        let game = game(&["abcdef"]);
+
+        // 10 columns less 2 on each side leaves 6, just enough for the text.
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(10, 7));
+        let positions = pixels
+            .iter()
+            .map(|p| (p.pos.x, p.pos.y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![(3, 3), (4, 3), (5, 3), (6, 3), (7, 3), (3, 4)]
+        );
+
+        // Too much padding still leaves a column and a row.
+        assert!(!self::pixels(&game, &config, false, None, ScreenSize::new(3, 3)).is_empty());
+    }
+
+    #[test]
+    fn wide_chars() {
+        let config = config();
+        let game = game(&["ab漢字c"]);
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(20, 3));
+        let xs = pixels.iter().map(|p| p.pos.x).collect::<Vec<_>>();
+        assert_eq!(xs, vec![6, 7, 8, 10, 12]);
+
+        // Neither 漢 nor 字 fit in the last column of their line.
+        let pixels = self::pixels(&game, &config, false, None, ScreenSize::new(4, 5));
+        let positions = pixels
+            .iter()
+            .map(|p| (p.pos.x, p.pos.y))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, 2), (2, 2), (1, 3), (1, 4), (3, 4)]);
+    }
+
+    #[test]
+    fn details_of_rejected_runs() {
+        let config =
+            Config::from_iter("tccst -ma 90 /".split_whitespace().map(str::to_owned)).unwrap();
+        let mut game = game(&["ab"]);
+        game.push('x');
+        game.push('b');
+        game.finish();
+
+        let text = |details| {
+            pixels(&game, &config, details, None, ScreenSize::new(200, 10))
+                .iter()
+                .map(|p| p.glyph)
+                .collect::<String>()
+        };
+        assert!(text(false).contains("Accuracy too low (50.00%) | D(etails)"));
+        assert!(!text(false).contains("mistakes: 1"));
+        assert!(text(true).contains("not recorded"));
+        assert!(text(true).contains("mistakes: 1"));
+    }
+
+    #[test]
+    fn error_breakdown() {
+        let config = config();
+        let mut game = game(&["ab", "cd"]);
+        "x  d".chars().for_each(|c| game.push(c));
+        game.finish();
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(200, 12));
+        let text = pixels.iter().map(|p| p.glyph).collect::<String>();
+        assert!(text.contains("wrong: 2 | extra: 0 | missed: 1 | accuracy"));
+    }
+
+    #[test]
+    fn perfect_run() {
+        let config = config();
+        let mut game = game(&["ab"]);
+        game.feed("ab");
+        assert!(game.is_finished());
+
+        let highlighted = |config: &Config, width| {
+            pixels(&game, config, false, None, ScreenSize::new(width, 20))
+                .iter()
+                .filter(|p| p.fg_color == config.palette.correct.fg)
+                .map(|p| p.glyph)
+                .collect::<String>()
+        };
+        assert_eq!(highlighted(&config, 200), PERFECT_RUN);
+
+        // Under the daily challenge line.
+        let mut config = config;
+        config.daily = Some("2026-10-15".into());
+        assert_eq!(highlighted(&config, 200), PERFECT_RUN);
+
+        // Wrapped over several rows, the spaces it wraps at are left out.
+        let wrapped = PERFECT_RUN
+            .chars()
+            .filter(|c| *c != ' ')
+            .collect::<String>();
+        let narrow = highlighted(&config, 12).replace(' ', "");
+        assert_eq!(narrow, wrapped);
+    }
+
+    #[test]
+    fn skipped_words_in_error_breakdown() {
+        let mut config = config();
+        config.skip_word_on_space = true;
+        let options = GameOptions::from(&config);
+        let mut game = Game::new(&["abc".into(), "d".into()], options).unwrap();
+        "a d".chars().for_each(|c| game.push(c));
+        game.finish();
+
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(200, 12));
+        let text = pixels.iter().map(|p| p.glyph).collect::<String>();
+        assert!(text.contains("missed: 3 | skipped words: 1"));
+    }
+
+    #[test]
+    fn percent_typed() {
+        let mut config = config();
+        let mut game = game(&["abcd"]);
+        assert_eq!(percent(&game), 0);
+        game.push('a');
+        assert_eq!(percent(&game), 25);
+
+        config.percent = true;
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(20, 3));
+        let corner = pixels
+            .iter()
+            .filter(|p| p.pos.y == 0)
+            .map(|p| p.glyph)
+            .collect::<String>();
+        assert_eq!(corner, "25%");
+    }
+
+    #[test]
+    fn number_formatting() {
+        let mut game = game(&["abcd"]);
+        game.feed("abxd");
+        game.finish();
+
+        let text = |args: &str| {
+            let args = format!("tccst {} /", args);
+            let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+            pixels(&game, &config, false, None, ScreenSize::new(250, 20))
+                .iter()
+                .map(|p| p.glyph)
+                .collect::<String>()
+        };
+
+        assert!(text("").contains("accuracy: 75.00% |"));
+        assert!(text("--decimals 0").contains("accuracy: 75% |"));
+        assert!(text("--no-units --decimals 1").contains(" | 1 | 75.0 | 1 | "));
+    }
+
+    #[test]
+    fn live_wpm() {
+        let mut config = config();
+        config.live_wpm = true;
+        let game = game(&["abcd"]);
+
+        let size = ScreenSize::new(20, 5);
+        let mut pixels = pixels(&game, &config, false, None, size);
+        let row = |pixels: &[Pixel], y| {
+            pixels
+                .iter()
+                .filter(|p| p.pos.y == y)
+                .map(|p| (p.pos.x, p.glyph))
+                .collect::<Vec<_>>()
+        };
+        let wpm = row(&pixels, 1);
+        assert_eq!(wpm.first(), Some(&(15, '0')));
+        assert_eq!(wpm.iter().map(|p| p.1).collect::<String>(), "0 wpm");
+
+        // Out of the way of the label of the project.
+        pixels.extend(project_label("tccst".into(), size));
+        assert_eq!(row(&pixels, 1), wpm);
+        assert_eq!(
+            row(&pixels, 0).iter().map(|p| p.1).collect::<String>(),
+            "tccst"
+        );
+    }
+
+    #[test]
+    fn cursor_trail() {
+        let mut config = config();
+        config.trail = Some(3);
+        let mut game = game(&["abcdefgh"]);
+        game.feed("abxde");
+
+        // Four chars to a line: `abcd` and `efgh`.
+        let pixels = pixels(&game, &config, false, None, ScreenSize::new(5, 2));
+        let bg = |glyph: char| pixels.iter().find(|p| p.glyph == glyph).unwrap().bg_color;
+        assert_eq!(bg('a'), None);
+        assert_eq!(bg('e'), Some(Color::AnsiValue(TRAIL_BRIGHTEST)));
+        assert_eq!(bg('f'), config.palette.cursor.bg);
+        // The trail doesn't go back to the line above.
+        assert_eq!(bg('d'), None);
+
+        game.push('f');
+        game.push('g');
+        let pixels = self::pixels(&game, &config, false, None, ScreenSize::new(5, 2));
+        let bg = |glyph: char| pixels.iter().find(|p| p.glyph == glyph).unwrap().bg_color;
+        assert_eq!(bg('g'), Some(Color::AnsiValue(TRAIL_BRIGHTEST)));
+        assert_eq!(bg('f'), Some(trail_shade(1, 3)));
+        assert_eq!(bg('e'), Some(Color::AnsiValue(TRAIL_DARKEST)));
+        // The colours of the chars stay.
+        assert_eq!(
+            pixels.iter().find(|p| p.glyph == 'g').unwrap().fg_color,
+            config.palette.correct.fg
+        );
+
+        assert_eq!(trail_shade(0, 1), Color::AnsiValue(TRAIL_BRIGHTEST));
+    }
+}
@@ -0,0 +1,236 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::gamestate::Game;
+
+// -----------------------------------------------------------------------------
+//     - Replay -
+// -----------------------------------------------------------------------------
+/// The keystrokes of a run as written to the `--record` file:
+/// the first line has the ms each char of the text was typed at,
+/// tab separated, followed by the words, one per line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub words: Vec<String>,
+    /// Since the first keystroke, see `Game::typing_times`.
+    pub times: Vec<Duration>,
+}
+
+impl Replay {
+    /// The replay of a game played with the `words`.
+    pub fn new(words: &[String], game: &Game) -> Self {
+        Self {
+            words: words.to_vec(),
+            times: game.typing_times(),
+        }
+    }
+
+    pub fn to_lines(&self) -> String {
+        let times = self
+            .times
+            .iter()
+            .map(|time| time.as_millis().to_string())
+            .collect::<Vec<_>>()
+            .join("\t");
+
+        let mut lines = vec![times];
+        lines.extend(self.words.iter().cloned());
+        lines.join("\n") + "\n"
+    }
+
+    /// Parse the lines written by `to_lines`.
+    pub fn from_lines(lines: &str) -> Option<Self> {
+        let mut lines = lines.lines();
+        let times = lines
+            .next()?
+            .split('\t')
+            .filter(|ms| !ms.is_empty())
+            .map(|ms| ms.parse().ok().map(Duration::from_millis))
+            .collect::<Option<Vec<_>>>()?;
+        let words = lines.map(str::to_string).collect::<Vec<_>>();
+
+        match words.is_empty() {
+            true => None,
+            false => Some(Self { words, times }),
+        }
+    }
+}
+
+/// Write the replay to the file, replacing the replay of an earlier run.
+pub fn write(path: &Path, replay: &Replay) -> Result<()> {
+    fs::write(path, replay.to_lines())
+        .map_err(|e| Error::Write(format!("{}: {}", path.display(), e)))
+}
+
+pub fn read(path: &Path) -> Result<Replay> {
+    let lines =
+        fs::read_to_string(path).map_err(|e| Error::Read(format!("{}: {}", path.display(), e)))?;
+    Replay::from_lines(&lines).ok_or_else(|| {
+        Error::Read(format!(
+            "{}: not a keystroke log written by --record",
+            path.display()
+        ))
+    })
+}
+
+// -----------------------------------------------------------------------------
+//     - Compare -
+// -----------------------------------------------------------------------------
+/// Where one of the runs of a `--compare` took the lead for good.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lead {
+    /// 0 for the first run, 1 for the second.
+    pub run: usize,
+    /// The word it took the lead at, 1 is the first word.
+    pub word: usize,
+}
+
+/// Two runs of the same words, played back side by side at the speed they were typed.
+#[derive(Debug)]
+pub struct Compare {
+    pub text: Vec<char>,
+    times: [Vec<Duration>; 2],
+    start: Instant,
+}
+
+impl Compare {
+    /// Fails if the runs typed different words, there is no shared text to race over.
+    pub fn new(first: Replay, second: Replay, now: Instant) -> Result<Self> {
+        if first.words != second.words {
+            return Err(Error::ReplayMismatch);
+        }
+
+        Ok(Self {
+            text: first.words.join(" ").chars().collect(),
+            times: [first.times, second.times],
+            start: now,
+        })
+    }
+
+    /// How far into the text each run had typed at the same time in its run.
+    pub fn indices(&self, now: Instant) -> [usize; 2] {
+        let elapsed = now.saturating_duration_since(self.start);
+        let index = |times: &[Duration]| times.iter().take_while(|&&t| t <= elapsed).count();
+        [index(&self.times[0]), index(&self.times[1])]
+    }
+
+    /// How long each run took, up to its last typed char.
+    pub fn elapsed(&self) -> [Duration; 2] {
+        let last = |times: &[Duration]| times.last().copied().unwrap_or_default();
+        [last(&self.times[0]), last(&self.times[1])]
+    }
+
+    /// How many chars of the text each run typed.
+    pub fn typed(&self) -> [usize; 2] {
+        [self.times[0].len(), self.times[1].len()]
+    }
+
+    /// True once both runs are played back to their last char.
+    pub fn is_done(&self, now: Instant) -> bool {
+        self.indices(now) == self.typed()
+    }
+
+    /// Play both runs back from the start again.
+    pub fn restart(&mut self, now: Instant) {
+        self.start = now;
+    }
+
+    /// The run that got furthest first, and from which word on it stayed ahead.
+    /// A char one of the runs never typed is always reached first by the other.
+    pub fn lead(&self) -> Option<Lead> {
+        let last = self.typed().iter().max()?.checked_sub(1)?;
+        let leader = |i: usize| match (self.times[0].get(i), self.times[1].get(i)) {
+            (Some(a), Some(b)) if b < a => 1,
+            (None, Some(_)) => 1,
+            _ => 0,
+        };
+
+        let run = leader(last);
+        let from = (0..=last)
+            .rev()
+            .take_while(|&i| leader(i) == run)
+            .last()
+            .unwrap_or(last);
+        let word = self.text[..from].iter().filter(|&&c| c == ' ').count() + 1;
+
+        Some(Lead { run, word })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replay(words: &str, ms: &[u64]) -> Replay {
+        Replay {
+            words: words.split_whitespace().map(str::to_string).collect(),
+            times: ms.iter().copied().map(Duration::from_millis).collect(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        // Words are whole lines, a tab in one is kept.
+        let mut replay = replay("ab d", &[100, 250, 400]);
+        replay.words[0] = "ab\tc".into();
+        assert_eq!(Replay::from_lines(&replay.to_lines()), Some(replay));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.keys");
+        let replay = self::replay("ab cd", &[]);
+        write(&path, &replay).unwrap();
+        assert_eq!(read(&path).unwrap(), replay);
+
+        std::fs::write(&path, "not a log\n").unwrap();
+        assert!(read(&path).is_err());
+    }
+
+    #[test]
+    fn different_words() {
+        let first = replay("ab cd", &[100]);
+        let second = replay("ab ce", &[100]);
+        assert_eq!(
+            Compare::new(first, second, Instant::now()).unwrap_err(),
+            Error::ReplayMismatch
+        );
+    }
+
+    #[test]
+    fn play_back_both() {
+        let first = replay("ab cd", &[100, 200, 300, 400, 500]);
+        let second = replay("ab cd", &[300, 350, 380, 390]);
+        let start = Instant::now();
+        let mut compare = Compare::new(first, second, start).unwrap();
+
+        assert_eq!(compare.indices(start), [0, 0]);
+        assert_eq!(compare.indices(start + Duration::from_millis(300)), [3, 1]);
+        assert!(!compare.is_done(start + Duration::from_millis(450)));
+        assert!(compare.is_done(start + Duration::from_millis(500)));
+        assert_eq!(
+            compare.elapsed(),
+            [Duration::from_millis(500), Duration::from_millis(390)]
+        );
+
+        // The second run overtook in the second word, but never typed the last char.
+        assert_eq!(compare.lead(), Some(Lead { run: 0, word: 2 }));
+
+        let later = start + Duration::from_secs(10);
+        compare.restart(later);
+        assert_eq!(compare.indices(later), [0, 0]);
+    }
+
+    #[test]
+    fn lead_for_good() {
+        let first = replay("ab cd", &[100, 200, 300, 400, 500]);
+        let second = replay("ab cd", &[150, 250, 350, 360, 370]);
+        let compare = Compare::new(first, second, Instant::now()).unwrap();
+        assert_eq!(compare.lead(), Some(Lead { run: 1, word: 2 }));
+
+        let first = replay("ab cd", &[100, 200, 300, 400, 500]);
+        let second = replay("ab cd", &[150, 250, 350, 450, 550]);
+        let compare = Compare::new(first, second, Instant::now()).unwrap();
+        assert_eq!(compare.lead(), Some(Lead { run: 0, word: 1 }));
+    }
+}
@@ -1,91 +1,803 @@
-use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs::{read_to_string, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use rand::prelude::*;
 // use walkdir::WalkDir;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::WalkBuilder;
+use regex::Regex;
 
 use crate::config::Config;
 use crate::error::{Error, Result};
 
-fn find_files(path: PathBuf, required_ext: &str) -> Vec<PathBuf> {
-    let mut paths = Vec::new();
+// -----------------------------------------------------------------------------
+//     - File filter -
+// -----------------------------------------------------------------------------
+/// Decides which files under the project path are used for words.
+pub struct FileFilter {
+    extension: String,
+    file_names: Vec<String>,
+    include: GlobSet,
+    exclude: GlobSet,
+    name_regex: Option<Regex>,
+}
 
-    for entry in WalkBuilder::new(path).git_ignore(true).build() {
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
+impl FileFilter {
+    pub fn new(config: &Config) -> Result<Self> {
+        let inst = Self {
+            extension: config.file_extension.clone(),
+            file_names: config.file_names.clone(),
+            include: glob_set(&config.include)?,
+            exclude: glob_set(&config.exclude)?,
+            name_regex: config.name_regex.as_deref().map(name_regex).transpose()?,
         };
 
-        if !entry.file_type().unwrap().is_file() {
-            continue;
+        Ok(inst)
+    }
+
+    // `path` is relative to the project path.
+    // An exclude pattern always wins over an include pattern.
+    // Without any include patterns the file extension decides.
+    fn matches(&self, path: &Path) -> bool {
+        if self.exclude.is_match(path) {
+            return false;
+        }
+
+        if let Some(re) = &self.name_regex {
+            let name = path.file_name().and_then(|name| name.to_str());
+            if !name.map(|name| re.is_match(name)).unwrap_or(false) {
+                return false;
+            }
+        }
+
+        if !self.include.is_empty() {
+            return self.include.is_match(path);
+        }
+
+        // Files like `Makefile` have no extension to match.
+        let name = path.file_name().and_then(|name| name.to_str());
+        if name.map(|name| self.file_names.iter().any(|n| n == name)) == Some(true) {
+            return true;
+        }
+
+        has_extension(path, &self.extension)
+    }
+}
+
+// The extension of the path, in any case, is `extension`,
+// which is lowercase already, see `config::extension`.
+fn has_extension(path: &Path, extension: &str) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext.to_lowercase() == extension,
+        None => false,
+    }
+}
+
+pub fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(_) => return Err(Error::InvalidArgument(format!("invalid glob: {}", pattern))),
         }
+    }
+
+    builder
+        .build()
+        .map_err(|e| Error::InvalidArgument(e.to_string()))
+}
 
-        let path = entry.into_path();
-        if let Some(file_ext) = path.extension() {
-            match file_ext.to_str() {
-                Some(s) if s == required_ext => paths.push(path),
-                Some(_) | None => continue,
+pub fn name_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|_| Error::InvalidArgument(format!("invalid regex: {}", pattern)))
+}
+
+/// Files under `path` that match the `filter`.
+/// The directory is walked lazily, and the walk stops once `max_files` paths
+/// are found, unless `max_files` is zero.
+pub fn find_files(
+    path: PathBuf,
+    filter: &FileFilter,
+    max_files: usize,
+) -> impl Iterator<Item = PathBuf> + '_ {
+    // Sorted, so the same seed selects the same words on every machine.
+    let walker = WalkBuilder::new(&path)
+        .git_ignore(true)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let limit = match max_files {
+        0 => usize::MAX,
+        max => max,
+    };
+
+    walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.into_path())
+        .filter(move |file| filter.matches(file.strip_prefix(&path).unwrap_or(file)))
+        .take(limit)
+}
+
+// Number of bytes at the start of a file checked by `is_binary`.
+const BINARY_CHECK_LEN: usize = 512;
+
+// A cheap check before reading the whole file: a NUL byte or invalid
+// UTF-8 in the first few hundred bytes means it isn't text.
+fn is_binary(path: &Path) -> bool {
+    let mut buf = [0; BINARY_CHECK_LEN];
+    let len = match File::open(path).and_then(|mut file| file.read(&mut buf)) {
+        Ok(len) => len,
+        Err(_) => return true,
+    };
+
+    let prefix = &buf[..len];
+    if prefix.contains(&0) {
+        return true;
+    }
+
+    match std::str::from_utf8(prefix) {
+        Ok(_) => false,
+        // The check may have cut a multi-byte char in half.
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+// Files larger than this are as likely to be picked as one of this size,
+// with `--weight-by-size`.
+const MAX_FILE_WEIGHT: u64 = 64 * 1024;
+
+// The size of the file, so larger files are picked more often.
+// Every file gets some chance, even empty ones.
+fn file_weight(path: &Path) -> u64 {
+    let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+    size.clamp(1, MAX_FILE_WEIGHT)
+}
+
+// Some editors on Windows start files with a byte order mark,
+// which would otherwise end up as part of the first word.
+fn strip_bom(text: &str) -> &str {
+    text.strip_prefix('\u{FEFF}').unwrap_or(text)
+}
+
+// Replace tabs with spaces up to the next tab stop, so every char
+// in the line occupies exactly one column.
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for c in line.chars() {
+        match c {
+            '\t' if tab_width > 0 => {
+                let spaces = tab_width - column % tab_width;
+                (0..spaces).for_each(|_| expanded.push(' '));
+                column += spaces;
+            }
+            c => {
+                expanded.push(c);
+                column += 1;
             }
         }
     }
 
-    paths
+    expanded
+}
+
+// `#!/bin/sh`, but not the `#![...]` inner attribute of Rust.
+fn is_shebang(line: &str) -> bool {
+    line.starts_with("#!") && !line.starts_with("#![")
 }
 
-fn code_to_words(code: String) -> Vec<String> {
+// Remove the lines that are Rust attributes, `#[...]` and `#![...]`.
+// Attributes spanning several lines only lose their first line.
+fn strip_attributes(code: String) -> String {
+    code.lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("#[") && !line.starts_with("#![")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// With `keep_indent` the first word of every line starts with
+// the indentation of the line.
+fn code_to_words(code: String, tab_width: usize, comment: &str, keep_indent: bool) -> Vec<String> {
     let words = code
-        .split('\n')
-        .map(|line| match line.find("//") {
-            Some(pos) => &line[..pos],
+        .lines()
+        .enumerate()
+        .filter(|(i, line)| *i > 0 || !is_shebang(line))
+        .map(|(_, line)| expand_tabs(line, tab_width))
+        .map(|line| match line.find(comment) {
+            Some(pos) => line[..pos].to_string(),
             None => line,
         })
-        .map(|line| line.split_whitespace())
-        .flatten()
-        .map(String::from)
+        .flat_map(|line| {
+            let mut words = line
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            if let (true, Some(first)) = (keep_indent, words.first_mut()) {
+                first.insert_str(0, indent(&line));
+            }
+            words
+        })
         .collect::<Vec<_>>();
 
     words
 }
 
-fn choose_words(words: Vec<String>, word_count: usize, rng: &mut ThreadRng) -> Vec<String> {
+// Every non-empty line exactly as in the file, but for the whitespace at the
+// end that can't be seen to be typed. With `strip_comments` the comments
+// are left out, and the lines trimmed, unless `keep_indent` is set.
+fn code_to_lines(
+    code: String,
+    tab_width: usize,
+    comment: &str,
+    strip_comments: bool,
+    keep_indent: bool,
+) -> Vec<String> {
+    code.lines()
+        .map(|line| expand_tabs(line, tab_width))
+        .map(|line| match line.find(comment) {
+            Some(pos) if strip_comments => line[..pos].to_string(),
+            _ => line,
+        })
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            if keep_indent || !strip_comments {
+                line.trim_end().to_string()
+            } else {
+                line.trim().to_string()
+            }
+        })
+        .collect()
+}
+
+// The whitespace at the start of the line.
+fn indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+// The start of a line comment in the file. Files without an extension,
+// like a `Makefile` or a `Dockerfile`, use `#`.
+fn line_comment(path: &Path) -> &'static str {
+    match path.extension() {
+        Some(_) => "//",
+        None => "#",
+    }
+}
+
+// Prose has no comments to strip, and `//` may well be part of the text
+// (think of a url), so it is only split on whitespace.
+fn prose_to_words(text: String) -> Vec<String> {
+    text.split_whitespace().map(String::from).collect()
+}
+
+fn is_prose(path: &Path, config: &Config) -> bool {
+    config
+        .prose_extensions
+        .iter()
+        .any(|ext| has_extension(path, ext))
+}
+
+// Only the words with a digit or a symbol in them, e.g. `&mut` or `0x1f`,
+// as long as there are enough of them to meet the word count.
+fn symbol_words(words: Vec<String>, word_count: usize) -> Vec<String> {
+    let symbolic = words
+        .iter()
+        .filter(|word| word.chars().any(|c| !c.is_alphabetic()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if symbolic.len() >= word_count {
+        symbolic
+    } else {
+        words
+    }
+}
+
+// `word_count` words from anywhere in `words`, in random order.
+// Every word is picked at most once.
+fn scatter_words(words: Vec<String>, word_count: usize, rng: &mut impl Rng) -> Vec<String> {
+    let mut words = words.into_iter().map(Some).collect::<Vec<_>>();
+    rand::seq::index::sample(rng, words.len(), word_count.min(words.len()))
+        .into_iter()
+        .filter_map(|i| words[i].take())
+        .collect()
+}
+
+// `word_count` consecutive words, from `offset` if given or else from a random word.
+// An offset too close to the end starts at the last possible word instead.
+fn choose_words(
+    words: Vec<String>,
+    word_count: usize,
+    offset: Option<usize>,
+    rng: &mut impl Rng,
+) -> Vec<String> {
     let max = words.len() - word_count;
-    let to = rng.gen_range(0..=max);
+    let to = match offset {
+        Some(offset) => offset.min(max),
+        None => rng.gen_range(0..=max),
+    };
     words[to..to + word_count].into()
 }
 
-pub fn words(config: &Config, max_len: usize) -> Result<Vec<String>> {
-    let mut rng = thread_rng();
+// `count` consecutive non-empty lines of the code, starting at a random line.
+// The number of non-empty lines if there are fewer than `count`.
+fn choose_lines(
+    code: &str,
+    count: usize,
+    rng: &mut impl Rng,
+) -> std::result::Result<String, usize> {
+    let lines = code
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    if lines.len() < count {
+        return Err(lines.len());
+    }
 
-    let mut files = find_files(config.project_path.clone(), &config.file_extension);
-    if files.len() == 0 {
-        return Err(Error::NoFiles);
+    let from = rng.gen_range(0..=lines.len() - count);
+    Ok(lines[from..from + count].join("\n"))
+}
+
+// Pick `word_count` of the words, which has to be at least that many.
+fn pick_words(
+    words: Vec<String>,
+    word_count: usize,
+    config: &Config,
+    rng: &mut impl Rng,
+) -> Vec<String> {
+    let words = if config.symbols {
+        symbol_words(words, word_count)
+    } else {
+        words
+    };
+
+    let mut words = if config.scatter {
+        scatter_words(words, word_count, rng)
+    } else {
+        choose_words(words, word_count, config.offset, rng)
+    };
+    if config.shuffle {
+        words.shuffle(rng);
     }
 
-    loop {
-        match files.choose(&mut rng) {
-            Some(file) => {
-                let file_index = files.iter().position(|f| f == file).unwrap();
-                let file = files.remove(file_index);
-                let mut code = match read_to_string(file) {
-                    Ok(text) => text.trim().to_string(),
-                    Err(_) => return Err(Error::InvalidFile),
-                };
+    if config.caps {
+        capitalize(&mut words, rng);
+    }
 
-                if code.chars().count() > max_len {
-                    code = code[..max_len].to_string();
-                }
-                let words = code_to_words(code);
+    words
+}
+
+// Uppercase the first letter of about half the words, for shift key practice.
+fn capitalize(words: &mut [String], rng: &mut impl Rng) {
+    for word in words {
+        if rng.gen_bool(0.5) {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                *word = first.to_uppercase().chain(chars).collect();
+            }
+        }
+    }
+}
+
+// Split the words on `sep` too, like on spaces, e.g. `_` for snake_case drills.
+// The separators are dropped like the spaces.
+fn split_words(words: Vec<String>, sep: Option<&str>) -> Vec<String> {
+    match sep {
+        Some(sep) => words
+            .iter()
+            .flat_map(|word| word.split(|c| sep.contains(c)))
+            .filter(|word| !word.is_empty())
+            .map(String::from)
+            .collect(),
+        None => words,
+    }
+}
 
-                if words.len() < config.word_count {
-                    continue;
+// Only the words made of nothing but `chars`, for drills of a few keys.
+// Every word without `--chars`.
+fn only_chars(mut words: Vec<String>, chars: Option<&str>) -> Vec<String> {
+    if let Some(chars) = chars {
+        words.retain(|word| word.chars().all(|c| chars.contains(c)));
+    }
+
+    words
+}
+
+// False for chars that take up no room and can't be typed, but would count as typed.
+// Spaces and tabs are printable.
+fn is_printable(c: char) -> bool {
+    match c {
+        '\t' => true,
+        // Zero width spaces and joiners, and the byte order mark.
+        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => false,
+        c => !c.is_control(),
+    }
+}
+
+// With `printable_only`, the words without the chars that aren't printable,
+// and without the words that were nothing but. Every word otherwise.
+// Filtered before the words are counted and picked, like `only_chars`.
+fn printable(words: Vec<String>, printable_only: bool) -> Vec<String> {
+    if !printable_only {
+        return words;
+    }
+
+    words
+        .into_iter()
+        .map(|word| {
+            word.chars()
+                .filter(|&c| is_printable(c))
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// The files listed in the manifest at `path`, one per line, and the listed
+/// files that don't exist. Relative paths are relative to the manifest.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_manifest(path: &Path) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let text = read_to_string(path).map_err(|_| Error::Read(path.display().to_string()))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let files = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| dir.join(shellexpand::tilde(line).as_ref()))
+        .partition(|file| file.is_file());
+
+    Ok(files)
+}
+
+// -----------------------------------------------------------------------------
+//     - Git diff -
+// -----------------------------------------------------------------------------
+// The uncommitted changes under `project`, staged or not, with paths relative to it.
+fn git_diff(project: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["diff", "HEAD", "--no-color", "--no-ext-diff", "--relative"])
+        .current_dir(project)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        _ => Err(Error::Read(format!(
+            "the git diff of {}",
+            project.display()
+        ))),
+    }
+}
+
+/// The lines added in a unified diff, with the file they were added to.
+/// Removed lines and the context around the changes are left out.
+pub fn added_lines(diff: &str) -> Vec<(PathBuf, String)> {
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    // An added line can start with `++ ` too, only the line after `--- `
+    // names the file.
+    let mut old_side = false;
+
+    for line in diff.lines() {
+        let header = old_side;
+        old_side = line.starts_with("--- ");
+
+        if let (true, Some(path)) = (header, line.strip_prefix("+++ ")) {
+            // `b/` is the new side of the diff.
+            let path = path.strip_prefix("b/").unwrap_or(path);
+            files.push((PathBuf::from(path), String::new()));
+        } else if let (Some(added), Some((_, code))) = (line.strip_prefix('+'), files.last_mut()) {
+            code.push_str(added);
+            code.push('\n');
+        }
+    }
+
+    files.retain(|(_, code)| !code.is_empty());
+    files
+}
+
+/// The words of the first game of a `Selector`.
+pub fn words(config: &Config, default_max_chars: usize) -> Result<Vec<String>> {
+    Selector::new(config, default_max_chars)?.next()
+}
+
+// -----------------------------------------------------------------------------
+//     - Selector -
+// -----------------------------------------------------------------------------
+/// Characters of a file used with a `--seed` or `--daily`, in place of the
+/// terminal area, so a seed selects the same words in every terminal.
+pub const SEEDED_MAX_CHARS: usize = 80 * 24;
+
+/// Selects the words of every game in a session.
+///
+/// The files of a project are only looked for the first time the project is
+/// picked, and the files the words came from are only picked again once every
+/// other file was tried.
+pub struct Selector {
+    config: Config,
+    filter: FileFilter,
+    max_chars: usize,
+    rng: StdRng,
+    // The files of every project picked so far.
+    files: HashMap<PathBuf, Vec<PathBuf>>,
+    // The files of `config.files_from`, used instead of the files of the projects.
+    listed: Option<Vec<PathBuf>>,
+    missing: Vec<PathBuf>,
+    used: HashSet<PathBuf>,
+    project: Option<PathBuf>,
+}
+
+impl Selector {
+    /// Only the first `config.max_chars` characters of a file are used,
+    /// or `SEEDED_MAX_CHARS` with a seed, or `default_max_chars`.
+    pub fn new(config: &Config, default_max_chars: usize) -> Result<Self> {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let filter = FileFilter::new(config)?;
+        let (listed, missing) = match &config.files_from {
+            Some(path) => {
+                let (mut listed, missing) = read_manifest(path)?;
+                // Matched relative to the manifest, like the paths in it.
+                if config.filter_listed {
+                    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+                    listed.retain(|file| filter.matches(file.strip_prefix(dir).unwrap_or(file)));
                 }
+                (Some(listed), missing)
+            }
+            None => (None, Vec::new()),
+        };
+
+        let inst = Self {
+            config: config.clone(),
+            filter,
+            max_chars: config
+                .max_chars
+                .or(config.seed.map(|_| SEEDED_MAX_CHARS))
+                .unwrap_or(default_max_chars),
+            rng,
+            files: HashMap::new(),
+            listed,
+            missing,
+            used: HashSet::new(),
+            project: None,
+        };
+
+        Ok(inst)
+    }
 
-                let words = choose_words(words, config.word_count, &mut rng);
+    /// The files of `config.files_from` that don't exist, and are skipped.
+    pub fn missing(&self) -> &[PathBuf] {
+        &self.missing
+    }
+
+    /// The project the last words came from.
+    /// `None` if they came from `config.words_file` or `config.files_from`,
+    /// or before the first `next`.
+    pub fn project(&self) -> Option<&Path> {
+        self.project.as_deref()
+    }
+
+    /// With `config.verbatim` every "word" is a whole line.
+    /// Select `config.word_count` (but at least `config.min_words`) words
+    /// from a random file in one of the projects, picked at random,
+    /// or from `config.words_file` if that is set.
+    /// With `config.printable_only` control chars and invisible spaces
+    /// are taken out of the words.
+    // Not an `Iterator`, there is always a next game.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Vec<String>> {
+        self.project = None;
+        let config = &self.config;
+        let rng = &mut self.rng;
+        let word_count = config.word_count.max(config.min_words);
+
+        if let Some(path) = &config.words_file {
+            let words = match read_to_string(path) {
+                Ok(text) => prose_to_words(strip_bom(&text).to_string()),
+                Err(_) => return Err(Error::InvalidFile),
+            };
+            let words = split_words(words, config.word_sep.as_deref());
+            let words = only_chars(words, config.chars.as_deref());
+            let words = printable(words, config.printable_only);
+
+            if words.len() < word_count {
+                return Err(Error::InsufficientWords {
+                    requested: word_count,
+                    available: words.len(),
+                });
+            }
+
+            return Ok(pick_words(words, word_count, config, rng));
+        }
+
+        let max_chars = self.max_chars;
+        let filter = &self.filter;
+        let used = &mut self.used;
+        let project = match config.project_paths.choose(rng) {
+            Some(project) => project.clone(),
+            None => return Err(Error::PathMissing),
+        };
 
-                return Ok(words);
+        if config.git_diff {
+            let words = added_lines(&git_diff(&project)?)
+                .into_iter()
+                .filter(|(path, _)| filter.matches(path))
+                .flat_map(|(path, code)| {
+                    code_to_words(
+                        code,
+                        config.tab_width,
+                        line_comment(&path),
+                        config.keep_indent,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let words = split_words(words, config.word_sep.as_deref());
+            let words = only_chars(words, config.chars.as_deref());
+            let words = printable(words, config.printable_only);
+
+            if words.is_empty() {
+                return Err(Error::NoFiles);
+            }
+
+            if words.len() < word_count {
+                return Err(Error::InsufficientWords {
+                    requested: word_count,
+                    available: words.len(),
+                });
+            }
+
+            let words = pick_words(words, word_count, config, rng);
+            self.project = Some(project);
+            return Ok(words);
+        }
+
+        // Only the paths are collected, the files are read one at a time until
+        // one has enough words. Every file needs to be known to pick one at random:
+        // a prefix of the sorted walk would favour the first directories, and
+        // `weight_by_size` and picking without replacement need them all too.
+        // The walk happens once per project and session. Only an explicit
+        // `max_files` cuts it short, and then only the first paths are used.
+        let files = match &self.listed {
+            Some(listed) => listed.clone(),
+            None => self
+                .files
+                .entry(project.clone())
+                .or_insert_with(|| find_files(project.clone(), filter, config.max_files).collect())
+                .clone(),
+        };
+        if files.is_empty() {
+            return Err(Error::NoFiles);
+        }
+
+        let (mut files, mut used_files): (Vec<_>, Vec<_>) =
+            files.into_iter().partition(|file| !used.contains(file));
+
+        // Reported when no file has enough words, or lines with `--lines`.
+        let mut most_words = 0;
+        let mut most_lines = 0;
+
+        loop {
+            // Every file was used, start over.
+            if files.is_empty() && !used_files.is_empty() {
+                files.append(&mut used_files);
+                used.clear();
+            }
+
+            let file = if config.weight_by_size {
+                files.choose_weighted(rng, |f| file_weight(f)).ok()
+            } else {
+                files.choose(rng)
+            };
+
+            match file {
+                Some(file) => {
+                    let file_index = files.iter().position(|f| f == file).unwrap();
+                    let file = files.remove(file_index);
+                    if is_binary(&file) {
+                        continue;
+                    }
+
+                    let prose = is_prose(&file, config);
+                    let comment = line_comment(&file);
+                    let mut code = match read_to_string(&file) {
+                        Ok(text) => strip_bom(&text).trim().to_string(),
+                        Err(_) => return Err(Error::InvalidFile),
+                    };
+
+                    if code.chars().count() > max_chars {
+                        code = code.chars().take(max_chars).collect();
+                    }
+
+                    // With `--lines` every word of the lines is used, in order.
+                    if let Some(count) = config.lines {
+                        match choose_lines(&code, count, rng) {
+                            Ok(lines) => code = lines,
+                            Err(available) => {
+                                most_lines = most_lines.max(available);
+                                continue;
+                            }
+                        }
+                    }
+
+                    let words = if config.verbatim {
+                        code_to_lines(
+                            code,
+                            config.tab_width,
+                            comment,
+                            config.strip_comments,
+                            config.keep_indent,
+                        )
+                    } else if prose {
+                        prose_to_words(code)
+                    } else if config.strip_attributes {
+                        code_to_words(
+                            strip_attributes(code),
+                            config.tab_width,
+                            comment,
+                            config.keep_indent,
+                        )
+                    } else {
+                        code_to_words(code, config.tab_width, comment, config.keep_indent)
+                    };
+                    // Verbatim lines are typed as they are.
+                    let words = if config.verbatim {
+                        words
+                    } else {
+                        split_words(words, config.word_sep.as_deref())
+                    };
+                    let words = only_chars(words, config.chars.as_deref());
+                    let words = printable(words, config.printable_only);
+
+                    most_words = most_words.max(words.len());
+
+                    // A file of only comments and whitespace has no words at all.
+                    if words.is_empty() || (config.lines.is_none() && words.len() < word_count) {
+                        continue;
+                    }
+
+                    // `Config` rejects the rest of the picking with `--lines`.
+                    let words = match config.lines {
+                        Some(_) if config.caps => {
+                            let mut words = words;
+                            capitalize(&mut words, rng);
+                            words
+                        }
+                        Some(_) => words,
+                        None => pick_words(words, word_count, config, rng),
+                    };
+
+                    used.insert(file);
+                    // The listed files aren't from the project.
+                    if self.listed.is_none() {
+                        self.project = Some(project);
+                    }
+                    return Ok(words);
+                }
+                None => {
+                    return Err(match config.lines {
+                        Some(count) => Error::InsufficientLines {
+                            requested: count,
+                            available: most_lines,
+                        },
+                        None => Error::InsufficientWords {
+                            requested: word_count,
+                            available: most_words,
+                        },
+                    })
+                }
             }
-            None => return Err(Error::InsufficientWords),
         }
     }
 }
@@ -96,27 +808,721 @@ mod test {
 
     #[test]
     fn get_words() {
-        let words = code_to_words("a b c".into());
+        let words = code_to_words("a b c".into(), 4, "//", false);
         let expected = vec!["a".to_string(), "b".into(), "c".into()];
         assert_eq!(words, expected);
 
-        let words = code_to_words("a //b c d".into());
+        let words = code_to_words("a //b c d".into(), 4, "//", false);
         let expected = vec!["a".to_string()];
         assert_eq!(words, expected);
     }
 
+    #[test]
+    fn crlf_line_endings() {
+        let words = code_to_words("a b\r\nc d\r\n".into(), 4, "//", false);
+        let expected = vec!["a".to_string(), "b".into(), "c".into(), "d".into()];
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let words = code_to_words(strip_bom("\u{FEFF}fn main").into(), 4, "//", false);
+        let expected = vec!["fn".to_string(), "main".into()];
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn tabs_to_spaces() {
+        assert_eq!(expand_tabs("\tfn", 4), "    fn");
+        assert_eq!(expand_tabs("ab\tc", 4), "ab  c");
+        assert_eq!(expand_tabs("\t\tx", 2), "    x");
+        assert_eq!(expand_tabs("a\tb", 0), "a\tb");
+
+        let words = code_to_words("\tlet\ta = 1;".into(), 4, "//", false);
+        let expected = vec!["let".to_string(), "a".into(), "=".into(), "1;".into()];
+        assert_eq!(words, expected);
+    }
+
+    fn file_filter(args: &str) -> FileFilter {
+        let args = format!("tccst {} /", args);
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        FileFilter::new(&config).unwrap()
+    }
+
+    // Create the files (with some code in them) in a temporary directory.
+    fn project(files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "fn main() {}").unwrap();
+        }
+        dir
+    }
+
+    // File names relative to the project, sorted.
+    fn found(dir: &tempfile::TempDir, filter: &FileFilter) -> Vec<String> {
+        let mut files = find_files(dir.path().into(), filter, 0)
+            .map(|p| {
+                p.strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn max_files_cap() {
+        let names = (0..10).map(|i| format!("{}.rs", i)).collect::<Vec<_>>();
+        let dir = project(&names.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let filter = file_filter("");
+        assert_eq!(find_files(dir.path().into(), &filter, 3).count(), 3);
+        assert_eq!(find_files(dir.path().into(), &filter, 0).count(), 10);
+
+        // Walked in order of the file names.
+        let mut files = find_files(dir.path().into(), &filter, 0);
+        assert!(files.next().unwrap().ends_with("0.rs"));
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let dir = project(&["src/a.rs", "src/nested/b.rs", "src/c.txt", "tests/d.rs"]);
+
+        let filter = file_filter("--include src/**/*.rs");
+        assert_eq!(found(&dir, &filter), vec!["src/a.rs", "src/nested/b.rs"]);
+
+        let filter = file_filter("--include src/**/*.rs --exclude **/nested/**");
+        assert_eq!(found(&dir, &filter), vec!["src/a.rs"]);
+
+        let filter = file_filter("-t txt");
+        assert_eq!(found(&dir, &filter), vec!["src/c.txt"]);
+    }
+
+    #[test]
+    fn file_name_regex() {
+        let dir = project(&[
+            "src/mod.rs",
+            "src/foo_impl.rs",
+            "src/bar.rs",
+            "src/a/mod.rs",
+        ]);
+
+        let filter = file_filter("--name-regex _impl\\.rs$");
+        assert_eq!(found(&dir, &filter), vec!["src/foo_impl.rs"]);
+
+        let filter = file_filter("--name-regex ^mod\\.rs$");
+        assert_eq!(found(&dir, &filter), vec!["src/a/mod.rs", "src/mod.rs"]);
+    }
+
+    #[test]
+    fn max_chars_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "ä b c d e f").unwrap();
+        let args = format!("tccst -w 3 --max-chars 5 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+
+        // The terminal size is ignored when `max_chars` is set.
+        assert_eq!(words(&config, 1).unwrap(), vec!["ä", "b", "c"]);
+        assert_eq!(words(&config, 1000).unwrap(), vec!["ä", "b", "c"]);
+    }
+
+    #[test]
+    fn same_seed_same_words() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            let code = (0..50)
+                .map(|w| format!("w{}_{} ", i, w))
+                .collect::<String>();
+            std::fs::write(dir.path().join(format!("{}.rs", i)), code).unwrap();
+        }
+
+        let config = |seed: u64| {
+            let args = format!("tccst -w 5 --seed {} {}", seed, dir.path().display());
+            Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap()
+        };
+
+        let first = words(&config(7), 1000).unwrap();
+        assert_eq!(words(&config(7), 1000).unwrap(), first);
+        // Whatever the size of the terminal.
+        assert_eq!(words(&config(7), 10).unwrap(), first);
+        assert!((8..20).any(|seed| words(&config(seed), 1000).unwrap() != first));
+    }
+
     #[test]
     fn choose_some_words() {
         let words = vec!["a".to_string(), "b".into(), "c".into()];
         let mut rng = thread_rng();
-        let chosen = choose_words(words.clone(), 3, &mut rng);
+        let chosen = choose_words(words.clone(), 3, None, &mut rng);
         assert_eq!(words, chosen);
     }
 
+    #[test]
+    fn choose_from_offset() {
+        let words = ["a", "b", "c", "d"].map(String::from).to_vec();
+        let mut rng = thread_rng();
+        assert_eq!(
+            choose_words(words.clone(), 2, Some(0), &mut rng),
+            ["a", "b"]
+        );
+        assert_eq!(
+            choose_words(words.clone(), 2, Some(1), &mut rng),
+            ["b", "c"]
+        );
+        // Clamped to the last window.
+        assert_eq!(choose_words(words, 2, Some(9), &mut rng), ["c", "d"]);
+    }
+
     // #[test]
     // fn split_words() {
     //     let text = "a word::here".to_string();
     //     let words = code_to_words(text);
     //     assert_eq!(words.len(), 3);
     // }
+
+    #[test]
+    fn prose_keeps_slashes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.txt"),
+            "See https://example.com, it's nice.",
+        )
+        .unwrap();
+        let args = format!("tccst -w 4 -t txt {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+
+        assert_eq!(
+            words(&config, 1000).unwrap(),
+            vec!["See", "https://example.com,", "it's", "nice."]
+        );
+    }
+
+    #[test]
+    fn prefer_symbols() {
+        let words = code_to_words("let x = vec![1, 2];".into(), 4, "//", false);
+        assert_eq!(symbol_words(words.clone(), 3), vec!["=", "vec![1,", "2];"]);
+        // Not enough symbols, use all the words.
+        assert_eq!(symbol_words(words.clone(), 4), words);
+    }
+
+    #[test]
+    fn words_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        std::fs::write(&path, "fn\nimpl\nstruct enum\n").unwrap();
+
+        let config = |count: usize| {
+            let args = format!("tccst -w {} --words-file {}", count, path.display());
+            Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap()
+        };
+
+        assert_eq!(
+            words(&config(4), 1).unwrap(),
+            vec!["fn", "impl", "struct", "enum"]
+        );
+        assert_eq!(
+            words(&config(5), 1),
+            Err(Error::InsufficientWords {
+                requested: 5,
+                available: 4
+            })
+        );
+    }
+
+    #[test]
+    fn shuffle_with_seed() {
+        let dir = tempfile::tempdir().unwrap();
+        let code = (0..20).map(|w| format!("w{} ", w)).collect::<String>();
+        std::fs::write(dir.path().join("a.rs"), code).unwrap();
+
+        let config = |args: &str| {
+            let args = format!("tccst -w 20 {} {}", args, dir.path().display());
+            Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap()
+        };
+
+        let in_order = words(&config("--seed 3"), 1000).unwrap();
+        let shuffled = words(&config("--seed 3 --shuffle"), 1000).unwrap();
+        assert_ne!(shuffled, in_order);
+        assert_eq!(
+            words(&config("--seed 3 --shuffle"), 1000).unwrap(),
+            shuffled
+        );
+
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        let mut expected = in_order.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn capitalize_words() {
+        let words = (0..20).map(|w| format!("w{}", w)).collect::<Vec<_>>();
+        let mut capitalized = words.clone();
+        capitalize(&mut capitalized, &mut StdRng::seed_from_u64(1));
+
+        let mut again = words.clone();
+        capitalize(&mut again, &mut StdRng::seed_from_u64(1));
+        assert_eq!(again, capitalized);
+
+        assert!(capitalized.iter().any(|w| w.starts_with('W')));
+        assert!(capitalized.iter().any(|w| w.starts_with('w')));
+        for (word, capitalized) in words.iter().zip(&capitalized) {
+            assert_eq!(word.to_lowercase(), capitalized.to_lowercase());
+        }
+
+        // The game compares against the capitalized text.
+        let mut game = crate::gamestate::Game::new(&["Ab".into()], Default::default()).unwrap();
+        game.push('a');
+        assert_eq!(game.input(), &[('a', false)]);
+    }
+
+    #[test]
+    fn shebang() {
+        let words = code_to_words("#!/bin/sh\necho hi".into(), 4, "//", false);
+        assert_eq!(words, vec!["echo", "hi"]);
+
+        // Only the first line can be a shebang, and an inner attribute isn't one.
+        let words = code_to_words("#![no_std]\n#!x".into(), 4, "//", false);
+        assert_eq!(words, vec!["#![no_std]", "#!x"]);
+    }
+
+    #[test]
+    fn attributes() {
+        let code = "#![allow(dead_code)]\n    #[derive(Debug)]\nstruct A;";
+        let words = code_to_words(strip_attributes(code.into()), 4, "//", false);
+        assert_eq!(words, vec!["struct", "A;"]);
+    }
+
+    #[test]
+    fn min_words_floor() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "a b c d e f").unwrap();
+        let config = |args: &str| {
+            let args = format!("tccst {} {}", args, dir.path().display());
+            Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap()
+        };
+
+        assert_eq!(words(&config("-w 1 --min-words 3"), 1000).unwrap().len(), 3);
+        assert_eq!(words(&config("-w 4 --min-words 3"), 1000).unwrap().len(), 4);
+        assert_eq!(words(&config("-w 1"), 1000).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn file_without_words() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "// only\n  // comments\n").unwrap();
+        let args = format!("tccst -w 1 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        assert_eq!(
+            words(&config, 1000),
+            Err(Error::InsufficientWords {
+                requested: 1,
+                available: 0
+            })
+        );
+
+        std::fs::write(dir.path().join("b.rs"), "fn").unwrap();
+        assert_eq!(words(&config, 1000).unwrap(), vec!["fn"]);
+    }
+
+    #[test]
+    fn verbatim_lines() {
+        let code = "fn main() {\n\t// Say hi\n\tprintln!(\"hi\");   // twice?  \n\n}";
+        let lines = code_to_lines(code.into(), 4, "//", false, false);
+        assert_eq!(
+            lines,
+            vec![
+                "fn main() {",
+                "    // Say hi",
+                "    println!(\"hi\");   // twice?",
+                "}"
+            ]
+        );
+
+        let lines = code_to_lines(code.into(), 4, "//", true, false);
+        assert_eq!(lines, vec!["fn main() {", "println!(\"hi\");", "}"]);
+    }
+
+    #[test]
+    fn pick_a_project() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        std::fs::write(a.path().join("a.rs"), "from_a").unwrap();
+        std::fs::write(b.path().join("b.rs"), "from_b").unwrap();
+
+        let args = format!(
+            "tccst -w 1 --seed 1 {} {}",
+            a.path().display(),
+            b.path().display()
+        );
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        let mut selector = Selector::new(&config, 1000).unwrap();
+        assert_eq!(selector.project(), None);
+
+        let selections = (0..20)
+            .map(|_| {
+                let words = selector.next().unwrap();
+                (selector.project().unwrap().to_path_buf(), words)
+            })
+            .collect::<Vec<_>>();
+        for project in [a.path(), b.path()] {
+            assert!(selections.iter().any(|(p, _)| p == project));
+        }
+        for (project, words) in &selections {
+            let expected = if project == a.path() {
+                "from_a"
+            } else {
+                "from_b"
+            };
+            assert_eq!(words, &vec![expected]);
+        }
+    }
+
+    #[test]
+    fn skip_binary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), b"fn\0main").unwrap();
+        assert!(is_binary(&dir.path().join("a.rs")));
+
+        let args = format!("tccst -w 1 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        assert_eq!(
+            words(&config, 1000),
+            Err(Error::InsufficientWords {
+                requested: 1,
+                available: 0
+            })
+        );
+
+        // A multi-byte char cut off by the check is still text.
+        let text = format!("{}ä", "a".repeat(BINARY_CHECK_LEN - 1));
+        std::fs::write(dir.path().join("b.rs"), &text).unwrap();
+        assert!(!is_binary(&dir.path().join("b.rs")));
+        assert_eq!(words(&config, 1000).unwrap(), vec![text]);
+    }
+
+    #[test]
+    fn extension_in_any_case() {
+        let dir = project(&["foo.RS", "bar.Rs", "baz.rs", "notes.TXT"]);
+        assert_eq!(
+            found(&dir, &file_filter("-t rs")),
+            vec!["bar.Rs", "baz.rs", "foo.RS"]
+        );
+        assert_eq!(found(&dir, &file_filter("-t .TXT")), vec!["notes.TXT"]);
+
+        let args = "tccst /".split_whitespace().map(str::to_owned);
+        let config = Config::from_iter(args).unwrap();
+        assert!(is_prose(Path::new("README.MD"), &config));
+    }
+
+    #[test]
+    fn weight_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("small.rs"), "small").unwrap();
+        std::fs::write(dir.path().join("large.rs"), "large ".repeat(1000)).unwrap();
+
+        let large = |args: &str| {
+            (0..50)
+                .filter(|seed| {
+                    let args = format!(
+                        "tccst -w 1 --seed {} {} {}",
+                        seed,
+                        args,
+                        dir.path().display()
+                    );
+                    let config =
+                        Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+                    words(&config, 10_000).unwrap() == vec!["large"]
+                })
+                .count()
+        };
+
+        let weighted = large("--weight-by-size");
+        assert!(weighted > 45);
+        assert!(weighted > large(""));
+    }
+
+    #[test]
+    fn consecutive_lines() {
+        let code = "a b\n\n  c\nd e f\ng";
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let lines = choose_lines(code, 2, &mut rng).unwrap();
+            assert!(["a b\n  c", "  c\nd e f", "d e f\ng"].contains(&lines.as_str()));
+        }
+        assert_eq!(choose_lines(code, 5, &mut rng), Err(4));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.rs"), code).unwrap();
+        let words = |args: &str| {
+            let args = format!("tccst {} {}", args, dir.path().display());
+            let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+            super::words(&config, 10_000).unwrap()
+        };
+
+        // Every word of the lines, whatever -w is.
+        assert_eq!(words("--lines 4"), vec!["a", "b", "c", "d", "e", "f", "g"]);
+        assert_eq!(
+            words("--lines 4 --verbatim"),
+            vec!["a b", "  c", "d e f", "g"]
+        );
+        assert_eq!(
+            words("--lines 4 --caps").join(" ").to_lowercase(),
+            "a b c d e f g"
+        );
+
+        let args = format!("tccst --lines 6 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        assert_eq!(
+            super::words(&config, 10_000),
+            Err(Error::InsufficientLines {
+                requested: 6,
+                available: 4
+            })
+        );
+    }
+
+    #[test]
+    fn extensionless_file_names() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Makefile"), "# build it\nall: build").unwrap();
+        std::fs::write(dir.path().join("Dockerfile"), "FROM rust").unwrap();
+
+        let config = |args: &str| {
+            let args = format!("tccst {} {}", args, dir.path().display());
+            Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap()
+        };
+        assert_eq!(words(&config("-w 1"), 1000), Err(Error::NoFiles));
+
+        let words = words(&config("-w 2 --file-name Makefile"), 1000).unwrap();
+        assert_eq!(words, vec!["all:", "build"]);
+    }
+
+    #[test]
+    fn not_enough_words() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn main").unwrap();
+        std::fs::write(dir.path().join("b.rs"), "let x = 1;").unwrap();
+        let args = format!("tccst -w 10 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+
+        let error = words(&config, 1000).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Not enough words to meet word count: 10 requested, but the most in a single file is 4. Try a lower -w"
+        );
+    }
+
+    #[test]
+    fn keep_indent() {
+        let code = "def f(x):\n    return x  # done\n";
+        assert_eq!(
+            code_to_words(code.into(), 4, "#", true),
+            vec!["def", "f(x):", "    return", "x"]
+        );
+        assert_eq!(
+            code_to_lines(code.into(), 4, "#", true, true),
+            vec!["def f(x):", "    return x"]
+        );
+        assert_eq!(
+            code_to_lines(code.into(), 4, "#", true, false),
+            vec!["def f(x):", "return x"]
+        );
+    }
+
+    #[test]
+    fn words_from_a_diff() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,5 @@
+ fn unchanged() {}
+-fn removed() {}
++fn added() {} // a comment
++    let x = 1;
++++ y;
+diff --git a/Makefile b/Makefile
+new file mode 100644
+--- /dev/null
++++ b/Makefile
+@@ -0,0 +1 @@
++all: build # make
+diff --git a/gone.rs b/gone.rs
+deleted file mode 100644
+--- a/gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn gone() {}
+";
+
+        let files = added_lines(diff);
+        assert_eq!(
+            files,
+            vec![
+                (
+                    PathBuf::from("src/lib.rs"),
+                    "fn added() {} // a comment\n    let x = 1;\n++ y;\n".to_string()
+                ),
+                (PathBuf::from("Makefile"), "all: build # make\n".to_string()),
+            ]
+        );
+
+        let words = files
+            .into_iter()
+            .flat_map(|(path, code)| code_to_words(code, 4, line_comment(&path), false))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            vec!["fn", "added()", "{}", "let", "x", "=", "1;", "++", "y;", "all:", "build"]
+        );
+    }
+
+    #[test]
+    fn without_replacement() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "a a a").unwrap();
+        std::fs::write(dir.path().join("b.rs"), "b b b").unwrap();
+        let args = format!("tccst -w 3 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+
+        let mut selector = Selector::new(&config, 1000).unwrap();
+        let first = selector.next().unwrap();
+        let second = selector.next().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(selector.used.len(), 2);
+
+        // Once every file was used any of them can come up again.
+        selector.next().unwrap();
+        assert_eq!(selector.used.len(), 1);
+    }
+
+    #[test]
+    fn printable_only() {
+        assert_eq!(
+            printable(
+                vec![
+                    "fn\u{7}".into(),
+                    "\u{1b}".into(),
+                    "a\u{200B}b".into(),
+                    "\tx y".into()
+                ],
+                true
+            ),
+            vec!["fn", "ab", "\tx y"]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "let\u{8} x = 1;").unwrap();
+        let words = |args: &str| {
+            let args = format!("tccst -w 4 {} {}", args, dir.path().display());
+            let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+            words(&config, 1000).unwrap()
+        };
+
+        assert_eq!(words(""), vec!["let\u{8}", "x", "=", "1;"]);
+        assert_eq!(words("--printable-only"), vec!["let", "x", "=", "1;"]);
+
+        // A word of only control chars doesn't count towards -w.
+        std::fs::write(dir.path().join("a.rs"), "a \u{7} b c").unwrap();
+        for _ in 0..10 {
+            assert_eq!(words("-w 3 --printable-only"), vec!["a", "b", "c"]);
+        }
+    }
+
+    #[test]
+    fn chars_drill() {
+        let words = vec!["abc".into(), "cab".into(), "abcd".into(), "x".into()];
+        assert_eq!(only_chars(words.clone(), Some("abc")), vec!["abc", "cab"]);
+        assert_eq!(only_chars(words.clone(), None), words);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn bad(a: cab) -> abc { ba }").unwrap();
+        std::fs::write(dir.path().join("b.rs"), "fn main() { ccc }").unwrap();
+        let args = format!("tccst -w 2 --chars abc --seed 4 {}", dir.path().display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        let mut selector = Selector::new(&config, 1000).unwrap();
+
+        // `b.rs` has too few, every time.
+        for _ in 0..4 {
+            let words = selector.next().unwrap();
+            assert_eq!(words.len(), 2);
+            assert!(words.iter().all(|w| w.chars().all(|c| "abc".contains(c))));
+        }
+    }
+
+    #[test]
+    fn scatter() {
+        let words = (0..100).map(|w| format!("w{}", w)).collect::<Vec<_>>();
+        let scatter =
+            |seed: u64| scatter_words(words.clone(), 10, &mut StdRng::seed_from_u64(seed));
+
+        let scattered = scatter(7);
+        assert_eq!(scattered, scatter(7));
+        assert_ne!(scattered, scatter(8));
+        assert_eq!(scattered.len(), 10);
+        assert_eq!(scattered.iter().collect::<HashSet<_>>().len(), 10);
+        // Not a window of consecutive words.
+        let index = |w: &String| words.iter().position(|word| word == w).unwrap();
+        assert!(scattered
+            .windows(2)
+            .any(|w| index(&w[1]) != index(&w[0]) + 1));
+    }
+
+    #[test]
+    fn files_from_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.py"), "from_a").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/b.rs"), "from_b").unwrap();
+        std::fs::write(dir.path().join("c.rs"), "not_listed").unwrap();
+        let manifest = dir.path().join("drill.txt");
+        std::fs::write(&manifest, "# curated\na.py\n\nsrc/b.rs\ngone.rs\n").unwrap();
+
+        let (listed, missing) = read_manifest(&manifest).unwrap();
+        assert_eq!(
+            listed,
+            vec![dir.path().join("a.py"), dir.path().join("src/b.rs")]
+        );
+        assert_eq!(missing, vec![dir.path().join("gone.rs")]);
+
+        let args = format!("tccst -w 1 --seed 2 --files-from {}", manifest.display());
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        let mut selector = Selector::new(&config, 1000).unwrap();
+        assert_eq!(selector.missing(), &[dir.path().join("gone.rs")]);
+
+        let mut words = (0..2)
+            .flat_map(|_| selector.next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(selector.project(), None);
+        words.sort();
+        assert_eq!(words, vec!["from_a", "from_b"]);
+
+        // Only the listed files with the default extension, `rs`.
+        let args = format!("{} --filter-listed", args);
+        let config = Config::from_iter(args.split_whitespace().map(str::to_owned)).unwrap();
+        let mut selector = Selector::new(&config, 1000).unwrap();
+        for _ in 0..3 {
+            assert_eq!(selector.next().unwrap(), vec!["from_b"]);
+        }
+    }
+
+    #[test]
+    fn split_on_word_sep() {
+        let words = code_to_words("let foo_bar = a-b;".into(), 4, "//", false);
+        assert_eq!(
+            split_words(words.clone(), Some("_")),
+            vec!["let", "foo", "bar", "=", "a-b;"]
+        );
+        assert_eq!(
+            split_words(words.clone(), Some("_-")),
+            vec!["let", "foo", "bar", "=", "a", "b;"]
+        );
+        assert_eq!(split_words(words.clone(), None), words);
+    }
 }